@@ -0,0 +1,65 @@
+//! Tile collision with the old every-solid loop against the collision grid, on real levels.
+
+use std::fs;
+use std::hint::black_box;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::LdtkJson;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pillars_of_nature::collision::{collide_tiles, CollisionGrid, LevelGrid};
+
+const PLAYER_SIZE: Vec2 = Vec2::new(16., 32.);
+
+/// A player-sized actor on every other cell of the level, running right and falling.
+fn actors(grid: &LevelGrid) -> Vec<(Vec3, Vec2)>
+{
+    let mut actors = Vec::new();
+    for y in (0..grid.height).step_by(2)
+    {
+        for x in (0..grid.width).step_by(2)
+        {
+            let translation = Vec3::new(x as f32+0.5, y as f32+0.5, 0.)*grid.tile_size;
+            actors.push((translation, Vec2::new(4., -12.)));
+        }
+    }
+    actors
+}
+
+fn tile_collision(c: &mut Criterion)
+{
+    let project: LdtkJson = serde_json::from_str(
+        &fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/test_32.ldtk")).unwrap()
+    ).unwrap();
+
+    let mut group = c.benchmark_group("tile_collision");
+    for identifier in ["Yard", "Challenge"]
+    {
+        let level = project.levels.iter().find(|l| l.identifier == identifier).unwrap();
+        let level_grid = LevelGrid::from_level(level, Vec2::ZERO).unwrap();
+        let actors = actors(&level_grid);
+        let mut grid = CollisionGrid::default();
+        grid.insert(level_grid);
+        let solids: Vec<Vec3> = grid.solid_tiles().collect();
+
+        group.bench_with_input(BenchmarkId::new("every_solid", identifier), &actors, |b, actors| {
+            b.iter(|| {
+                for &(mut translation, mut vel) in actors
+                {
+                    black_box(collide_tiles(&mut translation, &mut vel, PLAYER_SIZE, |_, _| solids.iter().copied()));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("grid", identifier), &actors, |b, actors| {
+            b.iter(|| {
+                for &(mut translation, mut vel) in actors
+                {
+                    black_box(collide_tiles(&mut translation, &mut vel, PLAYER_SIZE, |center, size| grid.solids_in(center, size)));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tile_collision);
+criterion_main!(benches);
@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{GameState, TILE_SIZE};
+use crate::camera::CameraShake;
+use crate::collision::CollisionGrid;
+use crate::death::{Dead, DeathCause};
+use crate::dialogue::no_dialogue;
+use crate::health::Damage;
+use crate::input::{Action, PlayerId, PlayerInputs};
+use crate::interactables::Enemy;
+use crate::level::ActiveLevel;
+use crate::movement::{MovementConfig, MovementOverride};
+use crate::physics::{Actor, Vel};
+use crate::projectile::{ProjectileSpec, Shooter};
+use crate::player::{Player, PlayerLabel};
+
+/// What the fire power shoots.
+pub const FIRE_SHOT: ProjectileSpec = ProjectileSpec
+{
+    speed: 480.,
+    lifetime: 1.,
+    damage: 1,
+    gravity: 0.,
+    collides_with_solids: true,
+    size: Vec2::new(12.,12.),
+    color: Color::rgb(1., 0.47, 0.16)
+};
+/// Seconds between two fire shots of the same player.
+pub const FIRE_COOLDOWN: f32 = 0.4;
+/// Falling speed of a ground pound, in pixels per second.
+pub const POUND_SPEED: f32 = 1080.;
+/// How far from the landing spot a ground pound squashes enemies, in pixels.
+pub const POUND_RADIUS: f32 = 48.;
+/// How the camera shakes when a ground pound lands.
+pub const POUND_SHAKE: CameraShake = CameraShake { strength: 8., duration: 0.3 };
+/// Fastest a player slides down a wall, in pixels per second.
+pub const WALL_SLIDE_SPEED: f32 = 120.;
+/// Seconds after a wall jump in which the player is carried away from the wall.
+pub const WALL_JUMP_TIME: f32 = 0.15;
+
+/// Elemental powers a player got from totems, and their state.
+///
+/// Air is a double jump and lives on the [Actor]'s `jump_limit`; the others are used with
+/// their own [Action]s.
+#[derive(Component, Debug, Clone)]
+pub struct Abilities
+{
+    /// [Action::Shoot] throws a [FIRE_SHOT] ahead, using the player's [Shooter].
+    pub fire: bool,
+    /// [Action::Down] in the air is a ground pound, [Action::Build] places a block ahead.
+    pub earth: bool,
+    /// Holding towards a wall in the air slides down it, and jumping from there leaps off.
+    pub water: bool,
+    pub air: bool,
+    /// 1 when facing right, -1 when facing left.
+    pub facing: f32,
+    pub pounding: bool,
+    /// Side of the wall being slid down or just jumped off, or 0.
+    pub wall: f32,
+    pub wall_jump_time: f32,
+    /// The player's [EarthBlock], if one is placed.
+    pub block: Option<Entity>
+}
+
+impl Default for Abilities
+{
+    fn default() -> Self
+    {
+        Abilities
+        {
+            fire: false,
+            earth: false,
+            water: false,
+            air: false,
+            facing: 1.,
+            pounding: false,
+            wall: 0.,
+            wall_jump_time: 0.,
+            block: None
+        }
+    }
+}
+
+/// A solid block placed with the earth power. Each player has at most one.
+#[derive(Component)]
+pub struct EarthBlock
+{
+    pub cell: IVec2
+}
+
+/// The fire, earth and water powers; air is handled by the jump itself.
+pub struct AbilitiesPlugin;
+
+impl Plugin for AbilitiesPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_system(fire_shot.run_in_state(GameState::Gameplay).run_if(no_dialogue).after(PlayerLabel::Move))
+            .add_system(ground_pound.run_in_state(GameState::Gameplay).run_if(no_dialogue).after(PlayerLabel::Move))
+            .add_system(place_block.run_in_state(GameState::Gameplay).run_if(no_dialogue).after(PlayerLabel::Move))
+            .add_system(wall_slide.run_in_state(GameState::Gameplay).run_if(no_dialogue).after(PlayerLabel::Move));
+    }
+}
+
+/// Tile collision works in the IntGrid's frame, which is half a tile off from entities.
+fn to_grid(translation: Vec3) -> Vec3
+{
+    translation-Vec3::new(TILE_SIZE/2.,TILE_SIZE/2.,0.)
+}
+
+pub fn fire_shot(
+    mut player_q: Query<(&Transform, &Vel, &PlayerId, &mut Abilities, &mut Shooter, Entity), (With<Player>, Without<Dead>)>,
+    level_q: Query<Entity, With<Handle<LdtkLevel>>>,
+    active_level: Res<ActiveLevel>,
+    inputs: Res<PlayerInputs>,
+    mut commands: Commands)
+{
+    // Shots belong to the active level, so they go when it does.
+    let level = active_level.entity.and_then(|entity| level_q.get(entity).ok());
+    for (p_transform, p_vel, p_id, mut p_abilities, mut p_shooter, p_entity) in player_q.iter_mut()
+    {
+        if p_vel.0.x != 0.
+        {
+            p_abilities.facing = p_vel.0.x.signum();
+        }
+        if p_abilities.fire && inputs.player(*p_id).is_some_and(|input| input.just_pressed(Action::Shoot))
+        {
+            p_shooter.fire(
+                &mut commands,
+                level,
+                p_transform.translation+Vec3::new(p_abilities.facing*12.,4.,0.),
+                Vec2::new(p_abilities.facing, 0.),
+                p_entity
+            );
+        }
+    }
+}
+
+/// Slams down from the air and squashes tangible enemies next to the landing spot.
+pub fn ground_pound(
+    mut player_q: Query<(&Transform, &mut Vel, &Actor, &PlayerId, &mut Abilities), (With<Player>, Without<Dead>)>,
+    enemy_q: Query<(&Transform, &Enemy, Entity), Without<Player>>,
+    inputs: Res<PlayerInputs>,
+    mut shakes: EventWriter<CameraShake>,
+    mut damage: EventWriter<Damage>)
+{
+    for (p_transform, mut p_vel, p_actor, p_id, mut p_abilities) in player_q.iter_mut()
+    {
+        if p_abilities.earth && !p_actor.grounded && !p_abilities.pounding
+            && inputs.player(*p_id).is_some_and(|input| input.just_pressed(Action::Down))
+        {
+            p_abilities.pounding = true;
+        }
+        if !p_abilities.pounding
+        {
+            continue;
+        }
+        if p_actor.grounded
+        {
+            p_abilities.pounding = false;
+            shakes.send(POUND_SHAKE);
+            for (e_transform, e_stats, e_entity) in enemy_q.iter()
+            {
+                if e_stats.tangible
+                    && (e_transform.translation.x-p_transform.translation.x).abs() <= POUND_RADIUS
+                    && (e_transform.translation.y-p_transform.translation.y).abs() <= TILE_SIZE
+                {
+                    damage.send(Damage::lethal(e_entity, DeathCause::Squashed));
+                }
+            }
+            continue;
+        }
+        p_vel.0 = Vec2::new(0., -POUND_SPEED);
+    }
+}
+
+/// Raises a block in the tile ahead of the player, taking down their previous one.
+pub fn place_block(
+    mut player_q: Query<(&Transform, &Sprite, &PlayerId, &mut Abilities), (With<Player>, Without<Dead>)>,
+    actor_q: Query<(&Transform, &Sprite), With<Actor>>,
+    block_q: Query<&EarthBlock>,
+    level_q: Query<Entity, With<Handle<LdtkLevel>>>,
+    active_level: Res<ActiveLevel>,
+    inputs: Res<PlayerInputs>,
+    mut grid: ResMut<CollisionGrid>,
+    mut commands: Commands)
+{
+    for (p_transform, p_sprite, p_id, mut p_abilities) in player_q.iter_mut()
+    {
+        if !p_abilities.earth || !inputs.player(*p_id).is_some_and(|input| input.just_pressed(Action::Build))
+        {
+            continue;
+        }
+        // The first cell past the player's leading edge.
+        let edge = p_transform.translation+Vec3::new(p_abilities.facing*(p_sprite.custom_size.unwrap().x/2.-0.5),0.,0.);
+        let cell = (to_grid(edge).truncate()/TILE_SIZE).round().as_ivec2()+IVec2::new(p_abilities.facing as i32, 0);
+        let translation = (cell.as_vec2()*TILE_SIZE).extend(0.)+Vec3::new(TILE_SIZE/2.,TILE_SIZE/2.,0.);
+        if grid.is_solid(cell.x, cell.y) || actor_q.iter().any(|(a_transform, a_sprite)| collide(
+                a_transform.translation,
+                a_sprite.custom_size.unwrap_or(Vec2::splat(TILE_SIZE)),
+                translation,
+                Vec2::splat(TILE_SIZE-1.)
+            ).is_some())
+        {
+            continue;
+        }
+        let level = match active_level.entity.and_then(|entity| level_q.get(entity).ok())
+        {
+            Some(level) => level,
+            None => continue
+        };
+
+        if let Some(old) = p_abilities.block.take()
+        {
+            if let Ok(old_block) = block_q.get(old)
+            {
+                grid.set_solid(old_block.cell.x, old_block.cell.y, false);
+                commands.entity(old).despawn_recursive();
+            }
+        }
+        grid.set_solid(cell.x, cell.y, true);
+        commands.entity(level).with_children(|level| {
+            p_abilities.block = Some(level.spawn((
+                EarthBlock { cell },
+                SpriteBundle {
+                    sprite: Sprite { color: Color::rgb_u8(140, 96, 56), custom_size: Some(Vec2::splat(TILE_SIZE)), ..default() },
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                }
+            )).id());
+        });
+    }
+}
+
+/// Slows falls along a wall the player holds towards, and lets them jump off it.
+pub fn wall_slide(
+    mut player_q: Query<(&Transform, &mut Vel, &mut Actor, &Sprite, &PlayerId, &mut Abilities, Option<&MovementOverride>), (With<Player>, Without<Dead>)>,
+    inputs: Res<PlayerInputs>,
+    movement: Res<MovementConfig>,
+    grid: Res<CollisionGrid>,
+    time: Res<Time>)
+{
+    for (p_transform, mut p_vel, mut p_actor, p_sprite, p_id, mut p_abilities, p_overrides) in player_q.iter_mut()
+    {
+        let movement = movement.with(p_overrides);
+        if p_abilities.wall_jump_time > 0.
+        {
+            p_abilities.wall_jump_time = (p_abilities.wall_jump_time-time.delta_seconds()).max(0.);
+            p_vel.0.x = -p_abilities.wall*movement.run_speed;
+            continue;
+        }
+        p_abilities.wall = 0.;
+        let input = match inputs.player(*p_id)
+        {
+            Some(input) if p_abilities.water && !p_actor.grounded && !p_abilities.pounding => input,
+            _ => continue
+        };
+
+        let side = if input.pressed(Action::MoveRight) { 1. } else if input.pressed(Action::MoveLeft) { -1. } else { 0. };
+        let p_size = p_sprite.custom_size.unwrap();
+        if side == 0. || !grid.hits_solid(p_transform.translation+Vec3::new(side*(p_size.x/2.+1.),0.,0.), Vec2::new(1.,p_size.y-8.))
+        {
+            continue;
+        }
+        p_abilities.wall = side;
+
+        if input.just_pressed(Action::Jump)
+        {
+            p_vel.0.y = movement.jump_impulse;
+            p_actor.jumped = true;
+            p_abilities.wall_jump_time = WALL_JUMP_TIME;
+            p_vel.0.x = -side*movement.run_speed;
+        }
+        else if p_vel.0.y < -WALL_SLIDE_SPEED
+        {
+            p_vel.0.y = -WALL_SLIDE_SPEED;
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::hash::Hash;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::utils::{BoxedFuture, HashMap};
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::physics::{Actor, Vel};
+use crate::player::{BodyState, LegState, PlayerLabel};
+use crate::projectile::Shooter;
+
+/// Asset the player's animations are read from. Edits are picked up while the game runs.
+pub const PLAYER_ANIMATIONS_PATH: &str = "player.animations.ron";
+
+/// Frames of one state, played in order.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AnimationClip
+{
+    /// Indices into the layer's grid, left to right and top to bottom.
+    pub frames: Vec<usize>,
+    /// Frames per second.
+    pub fps: f32,
+    /// Whether to start over after the last frame, or hold it.
+    pub looping: bool
+}
+
+impl Default for AnimationClip
+{
+    fn default() -> Self
+    {
+        AnimationClip { frames: vec![0], fps: 8., looping: true }
+    }
+}
+
+impl AnimationClip
+{
+    /// Frame index to show `time` seconds into the clip.
+    pub fn frame(&self, time: f32) -> usize
+    {
+        if self.frames.is_empty()
+        {
+            return 0;
+        }
+        let step = (time*self.fps) as usize;
+        let step = if self.looping { step%self.frames.len() } else { step.min(self.frames.len()-1) };
+        self.frames[step]
+    }
+}
+
+/// One sprite sheet drawn on top of the character, with a clip for each state `S`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "S: Deserialize<'de> + Eq + Hash"))]
+pub struct AnimationLayer<S>
+{
+    pub texture: String,
+    pub tile_size: Vec2,
+    pub columns: usize,
+    pub rows: usize,
+    /// Where the layer sits relative to the character's center.
+    pub offset: Vec2,
+    pub clips: HashMap<S, AnimationClip>
+}
+
+/// Legs and upper body of a character, animated separately so one can run while the other shoots.
+#[derive(Deserialize, TypeUuid, Debug, Clone)]
+#[uuid = "d5a1f3c7-9e2b-4c86-b0d4-3a7e5f1c8b29"]
+pub struct CharacterAnimations
+{
+    pub legs: AnimationLayer<LegState>,
+    pub body: AnimationLayer<BodyState>
+}
+
+#[derive(Default)]
+pub struct CharacterAnimationsLoader;
+
+impl AssetLoader for CharacterAnimationsLoader
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let animations: CharacterAnimations = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(animations));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["animations.ron"]
+    }
+}
+
+/// Current states of an animated character, derived from its movement every frame.
+#[derive(Component)]
+pub struct CharacterAnimator
+{
+    pub animations: Handle<CharacterAnimations>,
+    pub legs: LegState,
+    pub body: BodyState,
+    /// 1 when facing right, -1 when facing left.
+    pub facing: f32,
+    /// Layer sprites spawned for the animations; empty until they have loaded.
+    layers: Vec<Entity>
+}
+
+impl CharacterAnimator
+{
+    pub fn new(animations: Handle<CharacterAnimations>) -> CharacterAnimator
+    {
+        CharacterAnimator { animations, legs: LegState::Idle, body: BodyState::Idle, facing: 1., layers: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyPart
+{
+    Legs,
+    Body
+}
+
+/// A child sprite showing one [AnimationLayer] of its parent's [CharacterAnimator].
+#[derive(Component)]
+pub struct AnimatedLayer
+{
+    pub part: BodyPart,
+    /// Seconds since the layer's state last changed.
+    pub time: f32
+}
+
+/// A sprite sheet playing one clip on its own, for things without states such as coins.
+#[derive(Component, Debug, Clone, Default)]
+pub struct SpriteAnimation
+{
+    pub clip: AnimationClip,
+    /// Seconds since the clip started.
+    pub time: f32
+}
+
+/// Sent when a character's legs change state, e.g. to play footsteps or a landing sound.
+#[derive(Debug, Clone, Copy)]
+pub struct LegStateChanged
+{
+    pub entity: Entity,
+    pub from: LegState,
+    pub to: LegState
+}
+
+/// Sent when a character's upper body changes state.
+#[derive(Debug, Clone, Copy)]
+pub struct BodyStateChanged
+{
+    pub entity: Entity,
+    pub from: BodyState,
+    pub to: BodyState
+}
+
+/// Fully transparent texture the character's own sprite switches to once its layers show.
+///
+/// The sprite itself stays, since its size and color are what collision, death and damage
+/// work with; the layers copy its color.
+#[derive(Resource)]
+pub struct BlankImage(pub Handle<Image>);
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, SystemLabel)]
+pub enum AnimationLabel
+{
+    /// [LegStateChanged] and [BodyStateChanged] for this frame are sent after this.
+    States
+}
+
+/// Layered sprite sheet animations for characters with a [CharacterAnimator].
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_asset::<CharacterAnimations>()
+            .init_asset_loader::<CharacterAnimationsLoader>()
+            .add_event::<LegStateChanged>()
+            .add_event::<BodyStateChanged>()
+            .add_startup_system(create_blank_image)
+            .add_system(update_character_states.run_in_state(GameState::Gameplay).after(PlayerLabel::Move).label(AnimationLabel::States))
+            .add_system(build_layers)
+            .add_system(animate_layers.after(AnimationLabel::States))
+            .add_system(play_sprite_animations);
+    }
+}
+
+pub fn create_blank_image(mut images: ResMut<Assets<Image>>, mut commands: Commands)
+{
+    let blank = Image::new_fill(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb
+    );
+    commands.insert_resource(BlankImage(images.add(blank)));
+}
+
+pub fn update_character_states(
+    mut character_q: Query<(Entity, &Vel, &Actor, Option<&Shooter>, &mut CharacterAnimator)>,
+    mut leg_events: EventWriter<LegStateChanged>,
+    mut body_events: EventWriter<BodyStateChanged>)
+{
+    for (c_entity, c_vel, c_actor, c_shooter, mut animator) in character_q.iter_mut()
+    {
+        if c_vel.0.x != 0.
+        {
+            animator.facing = c_vel.0.x.signum();
+        }
+        let legs = if !c_actor.grounded
+        {
+            LegState::Airborne
+        }
+        else if c_vel.0.x != 0.
+        {
+            LegState::Running
+        }
+        else
+        {
+            LegState::Idle
+        };
+        let body = if c_shooter.is_some_and(|shooter| shooter.shooting())
+        {
+            BodyState::Shooting
+        }
+        else
+        {
+            match legs
+            {
+                LegState::Idle => BodyState::Idle,
+                LegState::Running => BodyState::Running,
+                LegState::Airborne => BodyState::Airborne
+            }
+        };
+
+        if legs != animator.legs
+        {
+            leg_events.send(LegStateChanged { entity: c_entity, from: animator.legs, to: legs });
+            animator.legs = legs;
+        }
+        if body != animator.body
+        {
+            body_events.send(BodyStateChanged { entity: c_entity, from: animator.body, to: body });
+            animator.body = body;
+        }
+    }
+}
+
+fn layer_bundle<S>(layer: &AnimationLayer<S>, z: f32, asset_server: &AssetServer, atlases: &mut Assets<TextureAtlas>) -> SpriteSheetBundle
+{
+    let atlas = TextureAtlas::from_grid(asset_server.load(layer.texture.as_str()), layer.tile_size, layer.columns, layer.rows, None, None);
+    SpriteSheetBundle {
+        texture_atlas: atlases.add(atlas),
+        transform: Transform::from_translation(layer.offset.extend(z)),
+        ..default()
+    }
+}
+
+/// Spawns the layer sprites once a character's animations have loaded, and again when they change.
+pub fn build_layers(
+    mut events: EventReader<AssetEvent<CharacterAnimations>>,
+    mut character_q: Query<(Entity, &mut CharacterAnimator, &mut Handle<Image>)>,
+    animations: Res<Assets<CharacterAnimations>>,
+    blank: Option<Res<BlankImage>>,
+    asset_server: Res<AssetServer>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+    mut commands: Commands)
+{
+    let changed: Vec<Handle<CharacterAnimations>> = events.iter().filter_map(|event| match event
+    {
+        AssetEvent::Modified { handle } => Some(handle.clone()),
+        _ => None
+    }).collect();
+
+    for (c_entity, mut animator, mut c_texture) in character_q.iter_mut()
+    {
+        if !animator.layers.is_empty() && !changed.contains(&animator.animations)
+        {
+            continue;
+        }
+        if let Some(loaded) = animations.get(&animator.animations)
+        {
+            for layer in animator.layers.drain(..)
+            {
+                commands.entity(layer).despawn_recursive();
+            }
+            let legs = commands.spawn((layer_bundle(&loaded.legs, 0.1, &asset_server, &mut atlases), AnimatedLayer { part: BodyPart::Legs, time: 0. })).id();
+            let body = commands.spawn((layer_bundle(&loaded.body, 0.2, &asset_server, &mut atlases), AnimatedLayer { part: BodyPart::Body, time: 0. })).id();
+            commands.entity(c_entity).push_children(&[legs, body]);
+            animator.layers = vec![legs, body];
+            if let Some(blank) = &blank
+            {
+                *c_texture = blank.0.clone();
+            }
+        }
+    }
+}
+
+pub fn animate_layers(
+    mut layer_q: Query<(&mut AnimatedLayer, &mut TextureAtlasSprite, &Parent)>,
+    character_q: Query<(&CharacterAnimator, &Sprite)>,
+    mut leg_events: EventReader<LegStateChanged>,
+    mut body_events: EventReader<BodyStateChanged>,
+    animations: Res<Assets<CharacterAnimations>>,
+    time: Res<Time>)
+{
+    let legs_changed: Vec<Entity> = leg_events.iter().map(|event| event.entity).collect();
+    let body_changed: Vec<Entity> = body_events.iter().map(|event| event.entity).collect();
+    for (mut layer, mut l_sprite, l_parent) in layer_q.iter_mut()
+    {
+        if let Ok((animator, c_sprite)) = character_q.get(l_parent.get())
+        {
+            let restarted = match layer.part
+            {
+                BodyPart::Legs => legs_changed.contains(&l_parent.get()),
+                BodyPart::Body => body_changed.contains(&l_parent.get())
+            };
+            layer.time = if restarted { 0. } else { layer.time+time.delta_seconds() };
+
+            if let Some(loaded) = animations.get(&animator.animations)
+            {
+                let clip = match layer.part
+                {
+                    BodyPart::Legs => loaded.legs.clips.get(&animator.legs),
+                    BodyPart::Body => loaded.body.clips.get(&animator.body)
+                };
+                if let Some(clip) = clip
+                {
+                    l_sprite.index = clip.frame(layer.time);
+                }
+            }
+            l_sprite.flip_x = animator.facing < 0.;
+            l_sprite.color = c_sprite.color;
+        }
+    }
+}
+
+pub fn play_sprite_animations(mut animation_q: Query<(&mut SpriteAnimation, &mut TextureAtlasSprite)>, time: Res<Time>)
+{
+    for (mut animation, mut sprite) in animation_q.iter_mut()
+    {
+        animation.time += time.delta_seconds();
+        sprite.index = animation.clip.frame(animation.time);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn clip(frames: &[usize], looping: bool) -> AnimationClip
+    {
+        AnimationClip { frames: frames.to_vec(), fps: 10., looping }
+    }
+
+    #[test]
+    fn frames_advance_with_time()
+    {
+        let running = clip(&[5, 4, 6, 4], true);
+        assert_eq!(running.frame(0.), 5);
+        assert_eq!(running.frame(0.05), 5);
+        assert_eq!(running.frame(0.15), 4);
+        assert_eq!(running.frame(0.25), 6);
+        assert_eq!(running.frame(0.35), 4);
+    }
+
+    #[test]
+    fn looping_clips_start_over_and_others_hold_the_last_frame()
+    {
+        assert_eq!(clip(&[5, 4, 6, 4], true).frame(0.45), 5);
+        assert_eq!(clip(&[4, 7], false).frame(0.15), 7);
+        assert_eq!(clip(&[4, 7], false).frame(10.), 7);
+    }
+
+    #[test]
+    fn empty_clips_show_the_first_tile()
+    {
+        assert_eq!(clip(&[], true).frame(1.), 0);
+    }
+
+    #[test]
+    fn player_clips_move_and_stay_on_the_sheet()
+    {
+        let animations: CharacterAnimations = ron::from_str(include_str!("../assets/player.animations.ron")).unwrap();
+        assert!(animations.legs.clips[&LegState::Running].frames.len() > 1);
+        assert!(animations.body.clips[&BodyState::Idle].frames.len() > 1);
+
+        let mut legs = animations.legs.clips.values().flat_map(|clip| clip.frames.iter());
+        assert!(legs.all(|&frame| frame < animations.legs.columns*animations.legs.rows));
+        let mut body = animations.body.clips.values().flat_map(|clip| clip.frames.iter());
+        assert!(body.all(|&frame| frame < animations.body.columns*animations.body.rows));
+    }
+}
@@ -0,0 +1,56 @@
+//! Checks an LDtk project for mistakes the game would only run into while playing.
+//!
+//! Usage: `pillars-validate [project.ldtk]`, defaulting to the game's own project. Every
+//! problem is printed with its level and entity; the exit code is 1 if there were any, and 2
+//! if the project could not be read. The game's own project is allowed its
+//! [KNOWN_PROBLEMS](pillars_of_nature::validate::KNOWN_PROBLEMS).
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+use bevy_ecs_ldtk::app::LdtkEntityMap;
+use bevy_ecs_ldtk::ldtk::LdtkJson;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::loading::LDTK_PROJECT_PATH;
+use pillars_of_nature::validate::validate_project;
+
+fn main() -> ExitCode
+{
+    let own_project = format!("assets/{}", LDTK_PROJECT_PATH);
+    let path = env::args().nth(1).unwrap_or_else(|| own_project.clone());
+    let project: LdtkJson = match fs::read_to_string(&path).map_err(|error| error.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|error| error.to_string()))
+    {
+        Ok(project) => project,
+        Err(error) =>
+        {
+            eprintln!("Could not read {}: {}", path, error);
+            return ExitCode::from(2);
+        }
+    };
+
+    // The game's plugins know which entities get spawned.
+    let app = HeadlessApp::new();
+    let (known, problems): (Vec<_>, Vec<_>) = validate_project(&project, app.world().non_send_resource::<LdtkEntityMap>())
+        .into_iter()
+        .partition(|problem| path == own_project && problem.is_known());
+
+    for problem in known.iter()
+    {
+        println!("{} (known)", problem);
+    }
+    for problem in problems.iter()
+    {
+        println!("{}", problem);
+    }
+    if problems.is_empty()
+    {
+        println!("{}: no problems found", path);
+        ExitCode::SUCCESS
+    }
+    else
+    {
+        eprintln!("{}: {} problem(s) found", path, problems.len());
+        ExitCode::FAILURE
+    }
+}
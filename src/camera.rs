@@ -0,0 +1,413 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::transform::TransformSystem;
+use bevy::utils::BoxedFuture;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::ldtk_fields::{ldtk_fields, FromLdtkFields};
+use crate::level::ActiveLevel;
+use crate::physics::{PhysicsLabel, Vel};
+use crate::player::Player;
+
+/// Asset the [CameraConfig] is read from. Edits are picked up while the game runs.
+pub const CAMERA_CONFIG_PATH: &str = "default.camera.ron";
+/// Width and height of the view when there is no window, as in headless runs.
+pub const DEFAULT_VIEW_SIZE: f32 = 512.;
+
+/// How the camera follows the players, in pixels and seconds.
+#[derive(Resource, Deserialize, TypeUuid, Debug, Clone, Copy, PartialEq)]
+#[uuid = "b6d2e5a1-8c4f-4e37-a0d9-3f1b7c62e815"]
+#[serde(default)]
+pub struct CameraConfig
+{
+    /// Time the camera takes to close most of the distance to where it should be. 0 snaps.
+    pub smoothing: f32,
+    /// Size of the box in the middle of the view the players move in without the camera
+    /// following.
+    pub dead_zone_width: f32,
+    pub dead_zone_height: f32,
+    /// Seconds of the players' horizontal speed the camera looks ahead by.
+    pub look_ahead: f32,
+    pub max_look_ahead: f32,
+    /// Room kept between the outermost players and the edge of the view.
+    pub frame_margin: f32,
+    /// How far the camera may zoom out to keep every player in view.
+    pub max_zoom_out: f32
+}
+
+impl Default for CameraConfig
+{
+    fn default() -> Self
+    {
+        CameraConfig
+        {
+            smoothing: 0.15,
+            dead_zone_width: 64.,
+            dead_zone_height: 96.,
+            look_ahead: 0.25,
+            max_look_ahead: 96.,
+            frame_margin: 64.,
+            max_zoom_out: 2.
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CameraConfigLoader;
+
+impl AssetLoader for CameraConfigLoader
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let config: CameraConfig = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["camera.ron"]
+    }
+}
+
+/// Handle keeping the loaded [CameraConfig] asset alive.
+#[derive(Resource)]
+pub struct CameraConfigHandle(pub Handle<CameraConfig>);
+
+/// Shakes the camera, e.g. when a player is hit.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraShake
+{
+    /// Farthest the view is thrown off at first, in pixels.
+    pub strength: f32,
+    /// Seconds it takes to die down.
+    pub duration: f32
+}
+
+#[derive(Component)]
+pub struct PlayerCamera;
+
+/// Where the camera is headed, kept between frames for smoothing.
+#[derive(Component)]
+pub struct CameraFollow
+{
+    /// Point the dead zone is centered on, `None` until the camera has first found the players.
+    pub focus: Option<Vec2>,
+    pub look_ahead: f32,
+    /// Center of the view before shaking, in the frame of the world entity.
+    pub position: Vec2,
+    pub zoom: f32,
+    shake_strength: f32,
+    shake: Timer
+}
+
+impl Default for CameraFollow
+{
+    fn default() -> Self
+    {
+        CameraFollow
+        {
+            focus: None,
+            look_ahead: 0.,
+            position: Vec2::ZERO,
+            zoom: 1.,
+            shake_strength: 0.,
+            shake: Timer::default()
+        }
+    }
+}
+
+impl CameraFollow
+{
+    /// How far the current shake throws the view off, 0 once it has died down.
+    pub fn shake_left(&self) -> f32
+    {
+        if self.shake.finished() || self.shake.duration().is_zero()
+        {
+            0.
+        }
+        else
+        {
+            self.shake_strength*self.shake.percent_left()
+        }
+    }
+}
+
+ldtk_fields! {
+    /// Keeps the view from scrolling past it in the directions it stops, as long as the players
+    /// are on the near side.
+    ///
+    /// Only walls in the active level count. Each one works along its whole row or column, like
+    /// the edge of the level.
+    #[derive(Component, Default, Debug, Clone, Copy)]
+    pub struct CameraWall
+    {
+        "StopNorth" => pub stop_north: bool,
+        "StopSouth" => pub stop_south: bool,
+        "StopEast" => pub stop_east: bool,
+        "StopWest" => pub stop_west: bool
+    }
+}
+
+ldtk_fields! {
+    /// Zooms the camera while the players are inside the area, above 1 out and below 1 in.
+    #[derive(Component, Debug, Clone, Copy)]
+    pub struct CameraZoom
+    {
+        "Zoom" => pub zoom: f32 = 1.
+    }
+}
+
+#[derive(Bundle)]
+pub struct CameraWallBundle
+{
+    pub wall: CameraWall,
+    pub sprite: SpriteBundle
+}
+
+#[derive(Bundle)]
+pub struct CameraZoomBundle
+{
+    pub zoom: CameraZoom,
+    pub sprite: SpriteBundle
+}
+
+/// An invisible sprite the size of the LDtk entity, which is how areas keep their size.
+fn area_sprite(entity_instance: &EntityInstance) -> SpriteBundle
+{
+    SpriteBundle
+    {
+        sprite: Sprite {
+            custom_size: Some(Vec2::new(entity_instance.width as f32, entity_instance.height as f32)),
+            color: Color::NONE,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+impl LdtkEntity for CameraWallBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        _: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> CameraWallBundle {
+        CameraWallBundle
+        {
+            wall: CameraWall::from_entity(entity_instance, layer_instance),
+            sprite: area_sprite(entity_instance)
+        }
+    }
+}
+
+impl LdtkEntity for CameraZoomBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        _: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> CameraZoomBundle {
+        CameraZoomBundle
+        {
+            zoom: CameraZoom::from_entity(entity_instance, layer_instance),
+            sprite: area_sprite(entity_instance)
+        }
+    }
+}
+
+/// Spawns the 2D camera and keeps every player in view.
+///
+/// The camera eases after the players once they leave a dead zone, looks ahead of where they
+/// run and zooms out to fit them all, or to the [CameraZoom] area they are in. The view stays
+/// inside the active level and behind any [CameraWall], and shakes on [CameraShake]. Tuned by
+/// the [CameraConfig] loaded from [CAMERA_CONFIG_PATH].
+///
+/// Follows the interpolated player position, so it runs late in `PostUpdate`.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_asset::<CameraConfig>()
+            .init_asset_loader::<CameraConfigLoader>()
+            .init_resource::<CameraConfig>()
+            .add_event::<CameraShake>()
+            .register_ldtk_entity::<CameraWallBundle>("CameraWall")
+            .register_ldtk_entity::<CameraZoomBundle>("CameraZoom")
+            .add_startup_system(spawn_camera)
+            .add_startup_system(load_camera_config)
+            .add_system(apply_camera_config)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                camera
+                    .run_in_state(GameState::Gameplay)
+                    .after(PhysicsLabel::Interpolate)
+                    .before(TransformSystem::TransformPropagate)
+            );
+    }
+}
+
+pub fn spawn_camera(mut commands: Commands)
+{
+    commands.spawn(Camera2dBundle::default()).insert((PlayerCamera, CameraFollow::default()));
+}
+
+pub fn load_camera_config(asset_server: Res<AssetServer>, mut commands: Commands)
+{
+    commands.insert_resource(CameraConfigHandle(asset_server.load(CAMERA_CONFIG_PATH)));
+}
+
+pub fn apply_camera_config(
+    mut events: EventReader<AssetEvent<CameraConfig>>,
+    handle: Option<Res<CameraConfigHandle>>,
+    configs: Res<Assets<CameraConfig>>,
+    mut config: ResMut<CameraConfig>)
+{
+    if let Some(handle) = handle
+    {
+        for event in events.iter()
+        {
+            match event
+            {
+                AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed } if *changed == handle.0 =>
+                {
+                    if let Some(loaded) = configs.get(changed)
+                    {
+                        *config = *loaded;
+                    }
+                },
+                _ => ()
+            }
+        }
+    }
+}
+
+/// Moves a view of `half` size centered at `position` inside `bounds`, or centers it on an axis
+/// where the bounds are smaller than the view.
+fn clamp_view(position: Vec2, half: Vec2, bounds: Rect) -> Vec2
+{
+    let clamp_axis = |position: f32, min: f32, max: f32, half: f32|
+    {
+        if max-min < half*2. { (min+max)/2. } else { position.clamp(min+half, max-half) }
+    };
+    Vec2::new(
+        clamp_axis(position.x, bounds.min.x, bounds.max.x, half.x),
+        clamp_axis(position.y, bounds.min.y, bounds.max.y, half.y)
+    )
+}
+
+pub fn camera(mut camera_q: Query<(&mut Transform, &mut OrthographicProjection, &mut CameraFollow), With<PlayerCamera>>,
+    player_q: Query<(&Transform, Option<&Vel>), (With<Player>, Without<PlayerCamera>)>,
+    world_q: Query<&Transform, (With<Handle<LdtkAsset>>, Without<PlayerCamera>, Without<Player>)>,
+    wall_q: Query<(&Transform, &Sprite, &CameraWall, &Parent), Without<PlayerCamera>>,
+    zoom_q: Query<(&Transform, &Sprite, &CameraZoom), Without<PlayerCamera>>,
+    active_level: Res<ActiveLevel>,
+    config: Res<CameraConfig>,
+    windows: Option<Res<Windows>>,
+    time: Res<Time>,
+    mut shakes: EventReader<CameraShake>)
+{
+    let (mut c_transform, mut projection, mut follow) = camera_q.single_mut();
+    for shake in shakes.iter()
+    {
+        // The strongest shake going wins.
+        if shake.strength >= follow.shake_left()
+        {
+            follow.shake_strength = shake.strength;
+            follow.shake = Timer::from_seconds(shake.duration, TimerMode::Once);
+        }
+    }
+    follow.shake.tick(time.delta());
+
+    // Frame every player: center on the box around them and zoom out until it fits.
+    let mut players = player_q.iter().map(|(p_transform, _)| p_transform.translation.truncate());
+    let first = match players.next()
+    {
+        Some(first) => first,
+        None => return
+    };
+    let (min, max) = players.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+    let center = (min+max)/2.;
+    let span = (max-min)+Vec2::splat(config.frame_margin*2.);
+    let view = windows.as_ref()
+        .and_then(|windows| windows.get_primary())
+        .map_or(Vec2::splat(DEFAULT_VIEW_SIZE), |window| Vec2::new(window.width(), window.height()));
+
+    // Teleported players, e.g. through a door, are cut to rather than panned after.
+    let snap = follow.focus.is_none_or(|focus| focus.distance(center) > view.max_element()*follow.zoom);
+    let blend = if snap || config.smoothing <= 0. { 1. } else { 1.-(-time.delta_seconds()/config.smoothing).exp() };
+
+    let zone_zoom = zoom_q.iter()
+        .find(|(z_transform, z_sprite, _)| Rect::from_center_size(z_transform.translation.truncate(), z_sprite.custom_size.unwrap_or(Vec2::ZERO)).contains(center))
+        .map_or(1., |(_, _, zone)| zone.zoom);
+    let target_zoom = zone_zoom.max((span/view).max_element().min(config.max_zoom_out));
+    follow.zoom += (target_zoom-follow.zoom)*blend;
+    let half = view/2.*follow.zoom;
+
+    let dead_zone = Vec2::new(config.dead_zone_width, config.dead_zone_height)/2.;
+    let focus = match follow.focus
+    {
+        Some(focus) if !snap => center-(center-focus).clamp(-dead_zone, dead_zone),
+        _ => center
+    };
+    follow.focus = Some(focus);
+
+    let speed = player_q.iter().filter_map(|(_, p_vel)| p_vel).map(|p_vel| p_vel.0.x).sum::<f32>()/player_q.iter().len() as f32;
+    let look_ahead = (speed*config.look_ahead).clamp(-config.max_look_ahead, config.max_look_ahead);
+    follow.look_ahead += (look_ahead-follow.look_ahead)*blend;
+
+    let target = focus+Vec2::new(follow.look_ahead, 0.);
+    let mut position = follow.position+(target-follow.position)*blend;
+
+    // The active level, narrowed by its camera walls on the side the players are on.
+    let mut bounds = active_level.bounds.unwrap_or(Rect { min: Vec2::splat(f32::MIN), max: Vec2::splat(f32::MAX) });
+    for (w_transform, w_sprite, wall, w_parent) in wall_q.iter()
+    {
+        if active_level.entity != Some(w_parent.get())
+        {
+            continue;
+        }
+        let w_rect = Rect::from_center_size(w_transform.translation.truncate(), w_sprite.custom_size.unwrap_or(Vec2::ZERO));
+        if wall.stop_west && center.x >= w_rect.min.x
+        {
+            bounds.min.x = bounds.min.x.max(w_rect.min.x);
+        }
+        if wall.stop_east && center.x <= w_rect.max.x
+        {
+            bounds.max.x = bounds.max.x.min(w_rect.max.x);
+        }
+        if wall.stop_south && center.y >= w_rect.min.y
+        {
+            bounds.min.y = bounds.min.y.max(w_rect.min.y);
+        }
+        if wall.stop_north && center.y <= w_rect.max.y
+        {
+            bounds.max.y = bounds.max.y.min(w_rect.max.y);
+        }
+    }
+    position = clamp_view(position, half, bounds);
+    follow.position = position;
+
+    let t = time.elapsed_seconds();
+    let shake = Vec2::new((t*71.).sin(), (t*53.).cos())*follow.shake_left();
+
+    // Players and levels are placed relative to the world entity, the camera is not.
+    let world = world_q.iter().next().map_or(Vec2::ZERO, |w_transform| w_transform.translation.truncate());
+    projection.scale = follow.zoom;
+    c_transform.translation.x = position.x+world.x+shake.x;
+    c_transform.translation.y = position.y+world.y+shake.y;
+}
@@ -0,0 +1,384 @@
+//! Tile collision against the IntGrid layer of the spawned levels.
+
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use bevy_ecs_ldtk::ldtk::Level;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::TILE_SIZE;
+use crate::level::LevelBounds;
+
+/// IntGrid layer holding the level's collision.
+pub const SOLID_LAYER: &str = "IntGrid";
+/// IntGrid value of a solid tile.
+pub const SOLID_VALUE: i32 = 1;
+
+/// Solid tiles of one level.
+pub struct LevelGrid
+{
+    pub level_iid: String,
+    /// Cell of the level's bottom left tile.
+    pub origin: IVec2,
+    pub width: i32,
+    pub height: i32,
+    pub tile_size: f32,
+    solid: Vec<bool>
+}
+
+impl LevelGrid
+{
+    /// Reads the [SOLID_LAYER] of `level`, placed at `offset` in the world, or `None` if the
+    /// level has no such layer.
+    pub fn from_level(level: &Level, offset: Vec2) -> Option<LevelGrid>
+    {
+        let layer = level
+            .layer_instances
+            .as_ref()?
+            .iter()
+            .find(|l| l.identifier == SOLID_LAYER)?;
+
+        let mut solid = vec![false; (layer.c_wid*layer.c_hei) as usize];
+        for (index, value) in layer.int_grid_csv.iter().enumerate()
+        {
+            if *value == SOLID_VALUE
+            {
+                // LDtk rows go top to bottom, ours bottom to top.
+                let x = index as i32 % layer.c_wid;
+                let y = layer.c_hei-1-index as i32 / layer.c_wid;
+                solid[(y*layer.c_wid+x) as usize] = true;
+            }
+        }
+
+        let tile_size = layer.grid_size as f32;
+        if offset%tile_size != Vec2::ZERO
+        {
+            warn!("Level {} is not placed on its {} pixel grid, its collision will be off", level.identifier, layer.grid_size);
+        }
+        Some(LevelGrid {
+            level_iid: level.iid.clone(),
+            origin: (offset/tile_size).round().as_ivec2(),
+            width: layer.c_wid,
+            height: layer.c_hei,
+            tile_size,
+            solid
+        })
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize>
+    {
+        let (x, y) = (x-self.origin.x, y-self.origin.y);
+        (x >= 0 && y >= 0 && x < self.width && y < self.height).then_some((y*self.width+x) as usize)
+    }
+}
+
+/// Solid tiles of every spawned level.
+///
+/// Cell `(x, y)` counts from the bottom left of the world and sits at `(x, y) * tile_size`,
+/// which is the layer-relative translation `bevy_ecs_ldtk` gives IntGrid tile entities, moved
+/// by where the level is in the world.
+#[derive(Resource)]
+pub struct CollisionGrid
+{
+    pub tile_size: f32,
+    levels: Vec<LevelGrid>
+}
+
+impl Default for CollisionGrid
+{
+    fn default() -> Self
+    {
+        CollisionGrid { tile_size: TILE_SIZE, levels: Vec::new() }
+    }
+}
+
+impl CollisionGrid
+{
+    /// Adds the tiles of a level, replacing any it had before.
+    pub fn insert(&mut self, level: LevelGrid)
+    {
+        self.remove(&level.level_iid);
+        self.tile_size = level.tile_size;
+        self.levels.push(level);
+    }
+
+    pub fn remove(&mut self, level_iid: &str)
+    {
+        self.levels.retain(|level| level.level_iid != level_iid);
+    }
+
+    /// Whether cell `(x, y)` is solid. Everything outside the levels is empty.
+    pub fn is_solid(&self, x: i32, y: i32) -> bool
+    {
+        self.levels.iter().any(|level| level.index(x, y).is_some_and(|index| level.solid[index]))
+    }
+
+    /// Makes cell `(x, y)` solid or empty, e.g. for blocks placed during play. Cells outside
+    /// the levels are left alone.
+    pub fn set_solid(&mut self, x: i32, y: i32, solid: bool)
+    {
+        for level in self.levels.iter_mut()
+        {
+            if let Some(index) = level.index(x, y)
+            {
+                level.solid[index] = solid;
+            }
+        }
+    }
+
+    /// Translations of every solid tile.
+    pub fn solid_tiles(&self) -> impl Iterator<Item = Vec3> + '_
+    {
+        self.levels.iter().flat_map(move |level| self.solids_between(
+            level.origin,
+            level.origin+IVec2::new(level.width-1, level.height-1)
+        ))
+    }
+
+    /// Translations of the solid tiles overlapping a box, including ones that only touch it.
+    pub fn solids_in(&self, center: Vec3, size: Vec2) -> impl Iterator<Item = Vec3> + '_
+    {
+        let half_tile = self.tile_size/2.;
+        let min = ((center.truncate()-size/2.+half_tile)/self.tile_size).floor().as_ivec2();
+        let max = ((center.truncate()+size/2.+half_tile)/self.tile_size).floor().as_ivec2();
+        self.solids_between(min, max)
+    }
+
+    /// Whether a box centered like an actor, half a tile off from the tiles, overlaps a solid.
+    pub fn hits_solid(&self, translation: Vec3, size: Vec2) -> bool
+    {
+        let center = translation-Vec3::new(TILE_SIZE/2.,TILE_SIZE/2.,0.);
+        self.solids_in(center, size).any(|s_translation| collide(
+            center,
+            size,
+            s_translation,
+            Vec2::splat(TILE_SIZE)
+        ).is_some())
+    }
+
+    fn solids_between(&self, min: IVec2, max: IVec2) -> impl Iterator<Item = Vec3> + '_
+    {
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| (x, y)))
+            .filter(move |&(x, y)| self.is_solid(x, y))
+            .map(move |(x, y)| Vec3::new(x as f32*self.tile_size, y as f32*self.tile_size, 0.))
+    }
+}
+
+/// Moves an actor out of the solid tiles it would run into this step, horizontal axis first.
+///
+/// `solids` is asked for the tiles around a box before each axis is resolved. Returning more
+/// tiles than that is fine, so handing out every solid tile of the level works too, only
+/// slower. Returns whether the actor landed on something.
+pub fn collide_tiles<F, I>(translation: &mut Vec3, vel: &mut Vec2, size: Vec2, mut solids: F) -> bool
+where
+    F: FnMut(Vec3, Vec2) -> I,
+    I: IntoIterator<Item = Vec3>
+{
+    let mut grounded = false;
+
+    // Tiles are centered on their translation, actors are offset by half a tile.
+    let offset = Vec3::new(-TILE_SIZE/2.,-TILE_SIZE/2.,0.);
+
+    // Cover both where the actor is and where it is going, since resolving one tile moves it.
+    let sweep = Vec3::new(vel.x/2.,0.,0.);
+    for s_translation in solids(*translation+offset+sweep, size+Vec2::new(vel.x.abs(),0.))
+    {
+        if collide(
+            *translation+Vec3::new(vel.x,0.,0.)+offset,
+            size,
+            s_translation,
+            Vec2::splat(TILE_SIZE)
+        ).is_some()
+        {
+            if translation.x-TILE_SIZE/2. > s_translation.x
+            {
+                translation.x -= (translation.x-size.x/2.-TILE_SIZE/2.)-(s_translation.x+TILE_SIZE/2.);
+                vel.x = 0.;
+            }
+            else if translation.x-TILE_SIZE/2. < s_translation.x
+            {
+                translation.x -= (translation.x+size.x/2.-TILE_SIZE/2.)-(s_translation.x-TILE_SIZE/2.);
+                vel.x = 0.;
+            }
+        }
+    }
+
+    let sweep = Vec3::new(0.,vel.y/2.,0.);
+    for s_translation in solids(*translation+offset+sweep, size+Vec2::new(0.,vel.y.abs()))
+    {
+        if collide(
+            *translation+Vec3::new(0.,vel.y,0.)+offset,
+            size,
+            s_translation,
+            Vec2::splat(TILE_SIZE)
+        ).is_some()
+        {
+            if translation.y-TILE_SIZE/2. > s_translation.y
+            {
+                translation.y -= (translation.y-size.y/2.-TILE_SIZE/2.)-(s_translation.y+TILE_SIZE/2.);
+                vel.y = 0.;
+                grounded = true;
+            }
+            else if translation.y-TILE_SIZE/2. < s_translation.y
+            {
+                translation.y -= (translation.y+size.y/2.-TILE_SIZE/2.)-(s_translation.y-TILE_SIZE/2.);
+                vel.y = 0.;
+            }
+        }
+    }
+
+    grounded
+}
+
+/// Adds the tiles of each level to the [CollisionGrid] once it has been placed in the world,
+/// and takes them out again when it despawns.
+pub fn build_collision_grid(
+    mut level_events: EventReader<LevelEvent>,
+    level_q: Query<(&Handle<LdtkLevel>, &LevelBounds), Added<LevelBounds>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    mut grid: ResMut<CollisionGrid>)
+{
+    for event in level_events.iter()
+    {
+        if let LevelEvent::Despawned(iid) = event
+        {
+            grid.remove(iid);
+        }
+    }
+    for (l_handle, l_bounds) in level_q.iter()
+    {
+        if let Some(level_grid) = ldtk_levels.get(l_handle)
+            .and_then(|ldtk_level| LevelGrid::from_level(&ldtk_level.level, l_bounds.rect.min))
+        {
+            grid.insert(level_grid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use bevy_ecs_ldtk::ldtk::LdtkJson;
+
+    use super::*;
+
+    /// A level from rows of `#` for solid and `.` for empty, written top to bottom.
+    fn grid(level_iid: &str, origin: IVec2, rows: &[&str]) -> LevelGrid
+    {
+        let width = rows[0].len() as i32;
+        let height = rows.len() as i32;
+        let solid = rows.iter().rev().flat_map(|row| row.chars().map(|c| c == '#')).collect();
+        LevelGrid { level_iid: level_iid.to_string(), origin, width, height, tile_size: TILE_SIZE, solid }
+    }
+
+    fn collision(levels: impl IntoIterator<Item = LevelGrid>) -> CollisionGrid
+    {
+        let mut collision = CollisionGrid::default();
+        for level in levels
+        {
+            collision.insert(level);
+        }
+        collision
+    }
+
+    #[test]
+    fn cells_count_from_the_origin_of_their_level()
+    {
+        let collision = collision([grid("a", IVec2::new(10, 5), &[
+            "#..",
+            "..#"
+        ])]);
+        assert!(collision.is_solid(12, 5));
+        assert!(collision.is_solid(10, 6));
+        assert!(!collision.is_solid(10, 5));
+        assert!(!collision.is_solid(2, 0));
+        // Outside the level, even where the rows would wrap around.
+        assert!(!collision.is_solid(13, 5));
+        assert!(!collision.is_solid(9, 6));
+        assert!(!collision.is_solid(10, 7));
+        assert_eq!(collision.solid_tiles().collect::<Vec<_>>(), [Vec3::new(384., 160., 0.), Vec3::new(320., 192., 0.)]);
+    }
+
+    #[test]
+    fn set_solid_only_changes_cells_inside_a_level()
+    {
+        let mut collision = collision([grid("a", IVec2::ZERO, &["..", ".."])]);
+        collision.set_solid(1, 1, true);
+        collision.set_solid(5, 5, true);
+        assert!(collision.is_solid(1, 1));
+        assert!(!collision.is_solid(5, 5));
+        collision.set_solid(1, 1, false);
+        assert_eq!(collision.solid_tiles().count(), 0);
+    }
+
+    #[test]
+    fn levels_are_replaced_and_removed_by_iid()
+    {
+        let mut collision = collision([
+            grid("a", IVec2::ZERO, &["#"]),
+            grid("b", IVec2::new(1, 0), &["#"])
+        ]);
+        collision.insert(grid("a", IVec2::ZERO, &["."]));
+        assert!(!collision.is_solid(0, 0));
+        assert!(collision.is_solid(1, 0));
+        assert_eq!(collision.solid_tiles().count(), 1);
+
+        collision.remove("b");
+        assert!(!collision.is_solid(1, 0));
+        assert_eq!(collision.solid_tiles().count(), 0);
+    }
+
+    #[test]
+    fn boxes_find_the_tiles_they_overlap()
+    {
+        let collision = collision([grid("a", IVec2::ZERO, &[
+            "....",
+            "....",
+            "####"
+        ])]);
+        // Tiles are centered on their cell, so the floor reaches up to y = 16.
+        let near: Vec<_> = collision.solids_in(Vec3::new(32., 31., 0.), Vec2::splat(32.)).collect();
+        assert_eq!(near, [Vec3::new(32., 0., 0.), Vec3::new(64., 0., 0.)]);
+        assert_eq!(collision.solids_in(Vec3::new(32., 64., 0.), Vec2::splat(16.)).count(), 0);
+
+        // Actors sit half a tile up and right of the box they collide with.
+        assert!(collision.hits_solid(Vec3::new(48., 40., 0.), Vec2::new(16., 32.)));
+        assert!(!collision.hits_solid(Vec3::new(48., 48., 0.), Vec2::new(16., 32.)));
+        assert!(!collision.hits_solid(Vec3::new(48., 200., 0.), Vec2::new(16., 32.)));
+    }
+
+    #[test]
+    fn actors_land_on_the_floor()
+    {
+        let collision = collision([grid("a", IVec2::ZERO, &[
+            "....",
+            "....",
+            "####"
+        ])]);
+        let mut translation = Vec3::new(48., 52., 0.);
+        let mut vel = Vec2::new(0., -8.);
+        let grounded = collide_tiles(&mut translation, &mut vel, Vec2::new(16., 32.), |center, size| collision.solids_in(center, size));
+        assert!(grounded);
+        assert_eq!(vel, Vec2::ZERO);
+        assert_eq!(translation, Vec3::new(48., 48., 0.));
+    }
+
+    #[test]
+    fn levels_are_read_bottom_row_first()
+    {
+        let project: LdtkJson = serde_json::from_str(
+            &std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/test_32.ldtk")).unwrap()
+        ).unwrap();
+        let level = project.levels.iter().find(|level| level.identifier == "Yard").unwrap();
+        let layer = level.layer_instances.as_ref().unwrap().iter().find(|l| l.identifier == SOLID_LAYER).unwrap();
+
+        let offset = Vec2::new(-64., 320.);
+        let collision = collision([LevelGrid::from_level(level, offset).unwrap()]);
+        for (index, value) in layer.int_grid_csv.iter().enumerate()
+        {
+            let x = index as i32 % layer.c_wid-2;
+            let y = layer.c_hei-1-index as i32 / layer.c_wid+10;
+            assert_eq!(collision.is_solid(x, y), *value == SOLID_VALUE, "cell {} of the layer", index);
+        }
+    }
+}
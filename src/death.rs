@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::health::Health;
+use crate::input::PlayerId;
+use crate::interactables::{Checkpoint, LitCheckpoints};
+use crate::level::{is_selected, parent_level, PlayerSpawn};
+use crate::physics::Vel;
+use crate::player::{player_color, LocalPlayers, Player, RespawnRule};
+
+/// How long a dead player stays frozen before respawning, in seconds.
+pub const DEATH_FREEZE: f32 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause
+{
+    /// Touched a `Killer` tile.
+    Spikes,
+    /// Ran into an enemy instead of landing on it.
+    Enemy,
+    /// Fell out of the bottom of the level.
+    Fall,
+    /// Hit by a projectile.
+    Projectile,
+    /// Landed on by a player, or caught in their ground pound. Only enemies die like this.
+    Squashed
+}
+
+/// Sent whenever a living player is killed, before any respawn happens.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerDied
+{
+    pub entity: Entity,
+    pub player_id: PlayerId,
+    pub cause: DeathCause
+}
+
+/// A player who died and is waiting to respawn. Input, physics and hazards skip them.
+#[derive(Component)]
+pub struct Dead
+{
+    pub cause: DeathCause,
+    pub timer: Timer
+}
+
+/// Turns [PlayerDied] into a short freeze and a respawn.
+///
+/// Players come back at the last [Checkpoint] lit in the current level, or at its
+/// [PlayerSpawn]. Under [RespawnRule::SharedLives] each respawn takes a life; a player who dies
+/// with none left is removed.
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_event::<PlayerDied>()
+            .add_system(kill_players.run_in_state(GameState::Gameplay))
+            .add_system(respawn_players.run_in_state(GameState::Gameplay));
+    }
+}
+
+pub fn kill_players(
+    mut died: EventReader<PlayerDied>,
+    mut player_q: Query<&mut Vel, (With<Player>, Without<Dead>)>,
+    mut commands: Commands)
+{
+    let mut killed = Vec::new();
+    for death in died.iter()
+    {
+        if killed.contains(&death.entity)
+        {
+            continue;
+        }
+        if let Ok(mut p_vel) = player_q.get_mut(death.entity)
+        {
+            p_vel.0 = Vec2::ZERO;
+            commands.entity(death.entity).insert(Dead {
+                cause: death.cause,
+                timer: Timer::from_seconds(DEATH_FREEZE, TimerMode::Once)
+            });
+            killed.push(death.entity);
+        }
+    }
+}
+
+pub fn respawn_players(
+    mut dead_q: Query<(&mut Dead, &mut Transform, &mut Vel, &mut Sprite, &PlayerId, Entity, Option<&mut Health>), With<Player>>,
+    checkpoint_q: Query<(&Transform, &Checkpoint, &Parent), Without<Player>>,
+    player_spawn_q: Query<(&Transform, &Parent), (With<PlayerSpawn>, Without<Player>)>,
+    level_q: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    level_selection: Res<LevelSelection>,
+    lit_checkpoints: Res<LitCheckpoints>,
+    mut local_players: ResMut<LocalPlayers>,
+    time: Res<Time>,
+    mut commands: Commands)
+{
+    for (mut dead, mut p_transform, mut p_vel, mut p_sprite, p_id, p_entity, p_health) in dead_q.iter_mut()
+    {
+        dead.timer.tick(time.delta());
+        p_vel.0 = Vec2::ZERO;
+
+        // Flash red and fade out while frozen.
+        let fade = 1.-dead.timer.percent();
+        p_sprite.color = Color::rgba(1., 0., 0., fade);
+
+        if !dead.timer.finished()
+        {
+            continue;
+        }
+        if local_players.respawn == RespawnRule::SharedLives
+        {
+            if local_players.lives == 0
+            {
+                commands.entity(p_entity).despawn_recursive();
+                continue;
+            }
+            local_players.lives -= 1;
+        }
+
+        let respawn_point = checkpoint_q.iter()
+            .find(|(_, checkpoint, parent)| lit_checkpoints.is_respawn_point(checkpoint)
+                && parent_level(parent, &level_q, &ldtk_levels).is_some_and(|level| is_selected(&level_selection, level)))
+            .map(|(c_transform, _, _)| c_transform.translation)
+            .or_else(|| player_spawn_q.iter()
+                .find(|(_, parent)| parent_level(parent, &level_q, &ldtk_levels).is_some_and(|level| is_selected(&level_selection, level)))
+                .map(|(ps_transform, _)| ps_transform.translation));
+        if let Some(respawn_point) = respawn_point
+        {
+            p_transform.translation.x = respawn_point.x;
+            p_transform.translation.y = respawn_point.y;
+        }
+        p_sprite.color = player_color(*p_id);
+        if let Some(mut p_health) = p_health
+        {
+            p_health.current = p_health.max;
+        }
+        commands.entity(p_entity).remove::<Dead>();
+    }
+}
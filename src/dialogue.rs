@@ -0,0 +1,398 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::sprite::collide_aabb::collide;
+use bevy::utils::{BoxedFuture, HashMap};
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::death::Dead;
+use crate::input::{Action, PlayerId, PlayerInputs};
+use crate::interactables::Npc;
+use crate::physics::Vel;
+use crate::player::Player;
+use crate::loading::FontAssets;
+use crate::triggers::GameFlags;
+
+/// Asset every conversation is read from. Edits are picked up while the game runs.
+pub const DIALOGUE_PATH: &str = "default.dialogue.ron";
+
+/// A jump to another node, taken only if the [GameFlags] allow it.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Goto
+{
+    /// Node to go to, or `None` to end the conversation.
+    pub node: Option<String>,
+    /// Flags that must all be set.
+    pub requires: Vec<String>,
+    /// Flags that must all be unset.
+    pub unless: Vec<String>
+}
+
+impl Goto
+{
+    pub fn allowed(&self, flags: &GameFlags) -> bool
+    {
+        self.requires.iter().all(|flag| flags.0.contains(flag))
+            && !self.unless.iter().any(|flag| flags.0.contains(flag))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Choice
+{
+    pub text: String,
+    pub goto: Goto
+}
+
+/// One line of a conversation.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DialogueNode
+{
+    pub speaker: String,
+    pub text: String,
+    /// Answers the player picks from. Ones whose [Goto] is not allowed are left out.
+    pub choices: Vec<Choice>,
+    /// Where to go when there are no choices; the first allowed one is taken.
+    pub next: Vec<Goto>,
+    /// Flags set as soon as the line is shown.
+    pub set_flags: Vec<String>
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Conversation
+{
+    /// Where the conversation starts; the first allowed one is taken.
+    pub start: Vec<Goto>,
+    pub nodes: HashMap<String, DialogueNode>
+}
+
+/// Every conversation, by the name NPCs refer to it with.
+#[derive(Deserialize, TypeUuid, Debug, Clone, Default)]
+#[uuid = "8e2c4d61-0b7a-4f3e-a5c9-6d1f2e8b7a40"]
+#[serde(transparent)]
+pub struct Dialogues(pub HashMap<String, Conversation>);
+
+#[derive(Default)]
+pub struct DialoguesLoader;
+
+impl AssetLoader for DialoguesLoader
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let dialogues: Dialogues = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(dialogues));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.ron"]
+    }
+}
+
+/// Handle keeping the loaded [Dialogues] asset alive.
+#[derive(Resource)]
+pub struct DialoguesHandle(pub Handle<Dialogues>);
+
+/// The conversation being shown. Players stand still while it exists.
+#[derive(Resource, Debug, Clone)]
+pub struct ActiveDialogue
+{
+    pub conversation: String,
+    pub node: String,
+    /// Index into the currently allowed choices.
+    pub choice: usize,
+    /// The player who started talking and picks the answers.
+    pub player_id: PlayerId
+}
+
+#[derive(Component)]
+pub struct DialogueBox;
+
+#[derive(Component)]
+pub struct DialogueText;
+
+/// Talking to NPCs with [Action::Interact].
+///
+/// Conversations come from [DIALOGUE_PATH] and are shown in a text box at the bottom of the
+/// screen. [Action::MoveLeft] and [Action::MoveRight] pick an answer, [Action::Interact]
+/// moves on.
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_asset::<Dialogues>()
+            .init_asset_loader::<DialoguesLoader>()
+            .add_startup_system(load_dialogues)
+            .add_system(npc_interact.run_in_state(GameState::Gameplay).run_if(no_dialogue))
+            .add_system(advance_dialogue.run_in_state(GameState::Gameplay).run_if_resource_exists::<ActiveDialogue>())
+            .add_system_to_stage(CoreStage::PostUpdate, draw_dialogue);
+    }
+}
+
+pub fn no_dialogue(dialogue: Option<Res<ActiveDialogue>>) -> bool
+{
+    dialogue.is_none()
+}
+
+pub fn load_dialogues(asset_server: Res<AssetServer>, mut commands: Commands)
+{
+    commands.insert_resource(DialoguesHandle(asset_server.load(DIALOGUE_PATH)));
+}
+
+fn first_allowed<'a>(gotos: &'a [Goto], flags: &GameFlags) -> Option<&'a Goto>
+{
+    gotos.iter().find(|goto| goto.allowed(flags))
+}
+
+/// Moves the active dialogue to `goto`, or ends it.
+fn enter_node(
+    goto: Option<&Goto>,
+    conversation: &Conversation,
+    mut dialogue: ActiveDialogue,
+    flags: &mut GameFlags,
+    commands: &mut Commands)
+{
+    match goto.and_then(|goto| goto.node.as_ref()).and_then(|node| conversation.nodes.get_key_value(node))
+    {
+        Some((name, node)) =>
+        {
+            for flag in node.set_flags.iter()
+            {
+                flags.0.insert(flag.to_owned());
+            }
+            dialogue.node = name.to_owned();
+            dialogue.choice = 0;
+            commands.insert_resource(dialogue);
+        },
+        None => commands.remove_resource::<ActiveDialogue>()
+    }
+}
+
+pub fn npc_interact(
+    mut player_q: Query<(&Transform, &PlayerId, &mut Vel), (With<Player>, Without<Dead>)>,
+    npc_q: Query<(&Transform, &Npc, &Sprite), Without<Player>>,
+    inputs: Res<PlayerInputs>,
+    handle: Option<Res<DialoguesHandle>>,
+    dialogues: Res<Assets<Dialogues>>,
+    mut flags: ResMut<GameFlags>,
+    mut commands: Commands)
+{
+    let mut talker = None;
+    for (p_transform, p_id, _) in player_q.iter()
+    {
+        if !inputs.player(*p_id).is_some_and(|input| input.just_pressed(Action::Interact))
+        {
+            continue;
+        }
+        for (n_transform, n_npc, n_sprite) in npc_q.iter()
+        {
+            if collide(
+                p_transform.translation,
+               Vec2::new(16.,32.),
+                n_transform.translation,
+              n_sprite.custom_size.unwrap()
+            ).is_some()
+            {
+                talker = Some((*p_id, n_npc.dialogue.to_owned()));
+            }
+        }
+    }
+
+    if let Some((player_id, name)) = talker
+    {
+        match handle.and_then(|handle| dialogues.get(&handle.0)).and_then(|dialogues| dialogues.0.get(&name))
+        {
+            Some(conversation) =>
+            {
+                for (_, _, mut p_vel) in player_q.iter_mut()
+                {
+                    p_vel.0.x = 0.;
+                }
+                let dialogue = ActiveDialogue { conversation: name, node: String::new(), choice: 0, player_id };
+                enter_node(first_allowed(&conversation.start, &flags), conversation, dialogue, &mut flags, &mut commands);
+            },
+            None => warn!("No conversation called {} in {}", name, DIALOGUE_PATH)
+        }
+    }
+}
+
+pub fn advance_dialogue(
+    dialogue: Res<ActiveDialogue>,
+    inputs: Res<PlayerInputs>,
+    handle: Option<Res<DialoguesHandle>>,
+    dialogues: Res<Assets<Dialogues>>,
+    mut flags: ResMut<GameFlags>,
+    mut commands: Commands)
+{
+    let conversation = handle.and_then(|handle| dialogues.get(&handle.0)).and_then(|dialogues| dialogues.0.get(&dialogue.conversation));
+    let node = conversation.and_then(|conversation| conversation.nodes.get(&dialogue.node));
+    let input = inputs.player(dialogue.player_id);
+    match (conversation, node, input)
+    {
+        (Some(conversation), Some(node), Some(input)) =>
+        {
+            let choices: Vec<&Choice> = node.choices.iter().filter(|choice| choice.goto.allowed(&flags)).collect();
+            if !choices.is_empty()
+            {
+                let mut choice = dialogue.choice.min(choices.len()-1);
+                if input.just_pressed(Action::MoveRight)
+                {
+                    choice = (choice+1)%choices.len();
+                }
+                if input.just_pressed(Action::MoveLeft)
+                {
+                    choice = (choice+choices.len()-1)%choices.len();
+                }
+                if choice != dialogue.choice
+                {
+                    commands.insert_resource(ActiveDialogue { choice, ..dialogue.clone() });
+                }
+                if input.just_pressed(Action::Interact)
+                {
+                    enter_node(Some(&choices[choice].goto), conversation, dialogue.clone(), &mut flags, &mut commands);
+                }
+            }
+            else if input.just_pressed(Action::Interact)
+            {
+                enter_node(first_allowed(&node.next, &flags), conversation, dialogue.clone(), &mut flags, &mut commands);
+            }
+        },
+        // The file changed underneath us, or the player's bindings are gone.
+        _ => commands.remove_resource::<ActiveDialogue>()
+    }
+}
+
+/// Keeps the text box in line with the [ActiveDialogue], spawning and removing it as needed.
+pub fn draw_dialogue(
+    dialogue: Option<Res<ActiveDialogue>>,
+    handle: Option<Res<DialoguesHandle>>,
+    dialogues: Res<Assets<Dialogues>>,
+    flags: Res<GameFlags>,
+    box_q: Query<Entity, With<DialogueBox>>,
+    mut text_q: Query<&mut Text, With<DialogueText>>,
+    fonts: Res<FontAssets>,
+    mut commands: Commands)
+{
+    let dialogue = match dialogue
+    {
+        Some(dialogue) if dialogue.is_changed() || box_q.is_empty() => dialogue,
+        Some(_) => return,
+        None =>
+        {
+            for d_box in box_q.iter()
+            {
+                commands.entity(d_box).despawn_recursive();
+            }
+            return;
+        }
+    };
+    let node = handle
+        .and_then(|handle| dialogues.get(&handle.0))
+        .and_then(|dialogues| dialogues.0.get(&dialogue.conversation))
+        .and_then(|conversation| conversation.nodes.get(&dialogue.node));
+
+    if let Some(node) = node
+    {
+        let font = fonts.main.clone();
+        let style = |color: Color| TextStyle { font: font.clone(), font_size: 20.0, color };
+        let mut sections = vec![
+            TextSection::new(format!("{}\n", node.speaker), style(Color::rgb_u8(255, 200, 80))),
+            TextSection::new(node.text.to_owned(), style(Color::WHITE))
+        ];
+        for (i, choice) in node.choices.iter().filter(|choice| choice.goto.allowed(&flags)).enumerate()
+        {
+            let (marker, color) = if i == dialogue.choice { ("> ", Color::WHITE) } else { ("  ", Color::GRAY) };
+            sections.push(TextSection::new(format!("\n{}{}", marker, choice.text), style(color)));
+        }
+
+        match text_q.get_single_mut()
+        {
+            Ok(mut text) => text.sections = sections,
+            Err(_) =>
+            {
+                commands.spawn((NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect { left: Val::Px(16.), right: Val::Px(16.), bottom: Val::Px(16.), ..default() },
+                        size: Size::new(Val::Auto, Val::Px(144.)),
+                        padding: UiRect::all(Val::Px(12.)),
+                        ..default()
+                    },
+                    background_color: Color::rgba_u8(16, 0, 16, 230).into(),
+                    ..default()
+                }, DialogueBox)).with_children(|d_box| {
+                    d_box.spawn((TextBundle::from_sections(sections), DialogueText));
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn goto(requires: &[&str], unless: &[&str]) -> Goto
+    {
+        Goto
+        {
+            node: Some("next".to_string()),
+            requires: requires.iter().map(|flag| flag.to_string()).collect(),
+            unless: unless.iter().map(|flag| flag.to_string()).collect()
+        }
+    }
+
+    fn flags(set: &[&str]) -> GameFlags
+    {
+        GameFlags(set.iter().map(|flag| flag.to_string()).collect())
+    }
+
+    #[test]
+    fn gotos_without_conditions_are_always_allowed()
+    {
+        assert!(goto(&[], &[]).allowed(&flags(&[])));
+        assert!(goto(&[], &[]).allowed(&flags(&["met_elder"])));
+    }
+
+    #[test]
+    fn gotos_require_every_flag()
+    {
+        let goto = goto(&["met_elder", "has_key"], &[]);
+        assert!(!goto.allowed(&flags(&[])));
+        assert!(!goto.allowed(&flags(&["met_elder"])));
+        assert!(goto.allowed(&flags(&["met_elder", "has_key", "gate_open"])));
+    }
+
+    #[test]
+    fn gotos_are_blocked_by_any_unless_flag()
+    {
+        let goto = goto(&["met_elder"], &["gate_open", "has_key"]);
+        assert!(goto.allowed(&flags(&["met_elder"])));
+        assert!(!goto.allowed(&flags(&["met_elder", "has_key"])));
+        assert!(!goto.allowed(&flags(&["met_elder", "gate_open"])));
+        assert!(!goto.allowed(&flags(&["gate_open"])));
+    }
+
+    #[test]
+    fn the_first_allowed_goto_is_taken()
+    {
+        let gotos = [goto(&["has_key"], &[]), goto(&[], &["gate_open"]), goto(&[], &[])];
+        assert!(std::ptr::eq(first_allowed(&gotos, &flags(&["has_key"])).unwrap(), &gotos[0]));
+        assert!(std::ptr::eq(first_allowed(&gotos, &flags(&[])).unwrap(), &gotos[1]));
+        assert!(std::ptr::eq(first_allowed(&gotos, &flags(&["gate_open"])).unwrap(), &gotos[2]));
+        assert!(first_allowed(&gotos[..1], &flags(&[])).is_none());
+    }
+}
@@ -0,0 +1,284 @@
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::death::Dead;
+use crate::input::PlayerInputs;
+use crate::interactables::{Door, DoorTarget};
+use crate::level::{is_selected, parent_level};
+use crate::physics::{Interpolated, Vel};
+use crate::player::Player;
+
+/// Seconds the screen takes to fade to black, and again to fade back in.
+pub const DOOR_FADE_TIME: f32 = 0.3;
+/// Seconds after coming out of a door before doors take players again.
+pub const DOOR_COOLDOWN: f32 = 0.5;
+/// Seconds to wait for the target door to spawn before coming out at the `PlayerSpawn`.
+pub const DOOR_ARRIVAL_TIMEOUT: f32 = 1.;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DoorPhase
+{
+    #[default]
+    Idle,
+    FadeOut,
+    /// The screen is black while the next level loads.
+    Swap,
+    FadeIn
+}
+
+/// The level swap a [Door] started, if any.
+///
+/// Player input is locked from the moment a door is entered until the screen has faded back
+/// in, and doors stay shut for [DOOR_COOLDOWN] after that.
+#[derive(Resource)]
+pub struct DoorTransition
+{
+    pub phase: DoorPhase,
+    /// Times the current phase.
+    pub timer: Timer,
+    pub cooldown: Timer,
+    pub destination: Option<LevelSelection>,
+    pub target: DoorTarget,
+    /// Door the players came out at, which stays shut until they have all stepped off it.
+    pub arrival: Option<Entity>
+}
+
+impl Default for DoorTransition
+{
+    fn default() -> Self
+    {
+        let mut cooldown = Timer::from_seconds(DOOR_COOLDOWN, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        DoorTransition
+        {
+            phase: DoorPhase::Idle,
+            timer: Timer::from_seconds(DOOR_FADE_TIME, TimerMode::Once),
+            cooldown,
+            destination: None,
+            target: DoorTarget::PlayerSpawn,
+            arrival: None
+        }
+    }
+}
+
+impl DoorTransition
+{
+    pub fn active(&self) -> bool
+    {
+        self.phase != DoorPhase::Idle
+    }
+
+    fn enter_phase(&mut self, phase: DoorPhase, seconds: f32)
+    {
+        self.phase = phase;
+        self.timer = Timer::from_seconds(seconds, TimerMode::Once);
+    }
+
+    /// How dark the screen is, from 0 to 1.
+    pub fn darkness(&self) -> f32
+    {
+        match self.phase
+        {
+            DoorPhase::Idle => 0.,
+            DoorPhase::FadeOut => self.timer.percent(),
+            DoorPhase::Swap => 1.,
+            DoorPhase::FadeIn => self.timer.percent_left()
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DoorFade;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, SystemLabel)]
+pub enum DoorLabel
+{
+    /// Doors are entered here, and player input is locked right after in the same frame.
+    Enter,
+    /// Players are placed at the target door here, before the transition moves on to the
+    /// next phase in the same frame.
+    Arrive
+}
+
+/// Takes players through open doors: fades out, swaps the level, places them at the target
+/// door and fades back in.
+pub struct DoorsPlugin;
+
+impl Plugin for DoorsPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<DoorTransition>()
+            .add_startup_system(spawn_door_fade)
+            .add_system(enter_door.run_in_state(GameState::Gameplay).label(DoorLabel::Enter))
+            .add_system(arrive_at_door.run_in_state(GameState::Gameplay).label(DoorLabel::Arrive))
+            .add_system(run_door_transition.run_in_state(GameState::Gameplay).after(DoorLabel::Arrive))
+            .add_system(lock_door_input.after(DoorLabel::Enter))
+            .add_system_to_stage(CoreStage::PostUpdate, draw_door_fade);
+    }
+}
+
+fn touches_door(p_transform: &Transform, d_transform: &Transform, d_sprite: &Sprite) -> bool
+{
+    collide(
+        p_transform.translation,
+        Vec2::new(16.,32.),
+        d_transform.translation,
+        d_sprite.custom_size.unwrap()
+    ).is_some()
+}
+
+pub fn enter_door(
+    player_q: Query<&Transform, (With<Player>, Without<Dead>)>,
+    door_q: Query<(Entity, &Transform, &Door, &Sprite), Without<Player>>,
+    mut transition: ResMut<DoorTransition>)
+{
+    if transition.active() || !transition.cooldown.finished()
+    {
+        return;
+    }
+    if let Some((_, a_transform, _, a_sprite)) = transition.arrival.and_then(|arrival| door_q.get(arrival).ok())
+    {
+        if !player_q.iter().any(|p_transform| touches_door(p_transform, a_transform, a_sprite))
+        {
+            transition.arrival = None;
+        }
+    }
+
+    for p_transform in player_q.iter()
+    {
+        for (d_entity, d_transform, door, d_sprite) in door_q.iter()
+        {
+            if !door.open || transition.arrival == Some(d_entity) || !touches_door(p_transform, d_transform, d_sprite)
+            {
+                continue;
+            }
+            if let Some(destination) = door.destination()
+            {
+                transition.destination = Some(destination);
+                transition.target = door.target.to_owned();
+                transition.enter_phase(DoorPhase::FadeOut, DOOR_FADE_TIME);
+                return;
+            }
+        }
+    }
+}
+
+pub fn run_door_transition(mut transition: ResMut<DoorTransition>, time: Res<Time>, mut commands: Commands)
+{
+    transition.timer.tick(time.delta());
+    match transition.phase
+    {
+        DoorPhase::Idle =>
+        {
+            transition.cooldown.tick(time.delta());
+        },
+        DoorPhase::FadeOut if transition.timer.finished() =>
+        {
+            if let Some(destination) = transition.destination.take()
+            {
+                commands.insert_resource(destination);
+            }
+            commands.insert_resource(NextState(GameState::MapLoad));
+            transition.enter_phase(DoorPhase::Swap, DOOR_ARRIVAL_TIMEOUT);
+        },
+        DoorPhase::FadeIn if transition.timer.finished() =>
+        {
+            transition.phase = DoorPhase::Idle;
+            transition.cooldown.reset();
+        },
+        _ => ()
+    }
+}
+
+/// Places players at the target door once the new level is in.
+pub fn arrive_at_door(
+    mut player_q: Query<(&mut Transform, &mut Vel, Option<&mut Interpolated>), With<Player>>,
+    door_q: Query<(Entity, &Transform, &Door, &Sprite, &Parent), Without<Player>>,
+    level_q: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    level_selection: Res<LevelSelection>,
+    mut transition: ResMut<DoorTransition>)
+{
+    if transition.phase != DoorPhase::Swap
+    {
+        return;
+    }
+    // Only doors of the level just entered, not of the old one or any neighbours still around.
+    let in_selected_level = |parent: &Parent| parent_level(parent, &level_q, &ldtk_levels)
+        .is_some_and(|level| is_selected(&level_selection, level));
+    let target_door = door_q.iter().find(|(_, _, door, _, parent)| match &transition.target
+    {
+        DoorTarget::PlayerSpawn => false,
+        DoorTarget::Door { entity_iid, .. } => door.iid == *entity_iid,
+        DoorTarget::Id(id) => door.door_id == Some(*id) && in_selected_level(parent)
+    });
+
+    match target_door
+    {
+        Some((d_entity, d_transform, _, d_sprite, _)) =>
+        {
+            // Standing on the floor in front of the door.
+            let arrival = Vec3::new(
+                d_transform.translation.x,
+                d_transform.translation.y-d_sprite.custom_size.unwrap().y/2.+16.,
+                0.
+            );
+            for (mut p_transform, mut p_vel, interpolated) in player_q.iter_mut()
+            {
+                p_transform.translation.x = arrival.x;
+                p_transform.translation.y = arrival.y;
+                p_vel.0 = Vec2::ZERO;
+                if let Some(mut interpolated) = interpolated
+                {
+                    interpolated.previous = p_transform.translation;
+                    interpolated.current = p_transform.translation;
+                }
+            }
+            transition.arrival = Some(d_entity);
+        },
+        None if transition.target == DoorTarget::PlayerSpawn => (),
+        // The door may not have spawned yet.
+        None if !transition.timer.finished() => return,
+        None => warn!("Could not find the door {:?} leads to, coming out at the PlayerSpawn", transition.target)
+    }
+    transition.enter_phase(DoorPhase::FadeIn, DOOR_FADE_TIME);
+}
+
+pub fn lock_door_input(transition: Res<DoorTransition>, mut inputs: ResMut<PlayerInputs>)
+{
+    if inputs.locked != transition.active()
+    {
+        inputs.locked = transition.active();
+    }
+}
+
+pub fn spawn_door_fade(mut commands: Commands)
+{
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            ..default()
+        },
+        background_color: Color::NONE.into(),
+        // Over the HUD, under the menus.
+        z_index: ZIndex::Global(5),
+        ..default()
+    }, DoorFade));
+}
+
+pub fn draw_door_fade(transition: Res<DoorTransition>, mut fade_q: Query<&mut BackgroundColor, With<DoorFade>>)
+{
+    if !transition.is_changed()
+    {
+        return;
+    }
+    for mut background in fade_q.iter_mut()
+    {
+        background.0 = *Color::rgb_u8(16, 0, 16).set_a(transition.darkness());
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{GameState, TILE_SIZE};
+use crate::collision::CollisionGrid;
+use crate::death::Dead;
+use crate::interactables::Enemy;
+use crate::level::LevelBounds;
+use crate::physics::{Actor, Vel};
+use crate::player::Player;
+
+/// How close a player has to be for a smart enemy to chase them, in pixels.
+pub const SIGHT_RADIUS: f32 = 6.*TILE_SIZE;
+
+/// Where an [Enemy] patrols from and which way it is heading.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Patrol
+{
+    /// Spawn translation in the frame of the world entity; the enemy patrols between here and
+    /// `move_distance` away.
+    pub origin: Vec3,
+    /// -1, 0 or 1 on each axis.
+    pub dir: Vec2,
+    pub chasing: bool
+}
+
+/// Moves enemies from their LDtk `MoveDistance` and `MoveSpeed` fields.
+///
+/// Both are in tiles. Enemies walk back and forth over their distance, or until something
+/// stops them if it is 0; ones with a vertical speed fly. Smart enemies also turn at walls and
+/// ledges and go after players within [SIGHT_RADIUS].
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_system(add_patrol)
+            .add_system(enemy_patrol.run_in_state(GameState::Gameplay));
+    }
+}
+
+/// Gives enemies their [Patrol] once they sit where they will stay.
+///
+/// Enemies of a level that has just spawned wait for [place_levels](crate::level::place_levels)
+/// to move them into place, see [LevelBounds].
+pub fn add_patrol(
+    enemy_q: Query<(Entity, &Transform, &Enemy, Option<&Parent>), Without<Patrol>>,
+    level_q: Query<Option<&LevelBounds>, With<Handle<LdtkLevel>>>,
+    mut commands: Commands)
+{
+    for (e_entity, e_transform, e_stats, e_parent) in enemy_q.iter()
+    {
+        if e_parent.is_some_and(|parent| matches!(level_q.get(parent.get()), Ok(None)))
+        {
+            continue;
+        }
+        commands.entity(e_entity).insert(Patrol {
+            origin: e_transform.translation,
+            dir: Vec2::new(
+                if e_stats.move_distance.x < 0. { -1. } else { 1. },
+                if e_stats.move_distance.y < 0. { -1. } else { 1. }
+            ),
+            chasing: false
+        });
+    }
+}
+
+/// Turns `dir` around at the ends of the range between `origin` and `origin+distance`.
+fn ping_pong(position: f32, origin: f32, distance: f32, dir: f32) -> f32
+{
+    if distance == 0.
+    {
+        return dir;
+    }
+    let (min, max) = (origin.min(origin+distance), origin.max(origin+distance));
+    if position >= max
+    {
+        -1.
+    }
+    else if position <= min
+    {
+        1.
+    }
+    else
+    {
+        dir
+    }
+}
+
+pub fn enemy_patrol(
+    mut enemy_q: Query<(&Transform, &Enemy, &mut Patrol, &mut Vel, &Actor, &mut Sprite), Without<Player>>,
+    player_q: Query<&Transform, (With<Player>, Without<Dead>)>,
+    grid: Res<CollisionGrid>)
+{
+    for (e_transform, e_stats, mut patrol, mut e_vel, e_actor, mut e_sprite) in enemy_q.iter_mut()
+    {
+        let position = e_transform.translation;
+        let speed = e_stats.move_speed*TILE_SIZE;
+        let flying = speed.y != 0.;
+        let e_size = e_sprite.custom_size.unwrap_or(Vec2::splat(TILE_SIZE));
+
+        let target = player_q.iter()
+            .map(|p_transform| p_transform.translation)
+            .filter(|p_translation| p_translation.distance(position) <= SIGHT_RADIUS)
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+        patrol.chasing = e_stats.smart && target.is_some();
+
+        let mut dir = patrol.dir;
+        match target
+        {
+            Some(target) if patrol.chasing =>
+            {
+                let delta = target-position;
+                dir.x = if delta.x.abs() < 1. { 0. } else { delta.x.signum() };
+                if flying
+                {
+                    dir.y = if delta.y.abs() < 1. { 0. } else { delta.y.signum() };
+                }
+            },
+            _ =>
+            {
+                // Back on patrol after a chase.
+                if dir.x == 0. { dir.x = 1.; }
+                if dir.y == 0. { dir.y = 1.; }
+                let distance = e_stats.move_distance*TILE_SIZE;
+                dir.x = ping_pong(position.x, patrol.origin.x, distance.x, dir.x);
+                if flying
+                {
+                    dir.y = ping_pong(position.y, patrol.origin.y, distance.y, dir.y);
+                }
+            }
+        }
+
+        if dir.x != 0. && speed.x != 0.
+        {
+            let wall = grid.hits_solid(position+Vec3::new(dir.x*(e_size.x/2.+1.),0.,0.), Vec2::new(1.,e_size.y-8.));
+            let ledge = !flying && e_actor.grounded
+                && !grid.hits_solid(position+Vec3::new(dir.x*(e_size.x/2.+1.),-e_size.y/2.-1.,0.), Vec2::new(1.,1.));
+            if e_stats.smart && (wall || ledge)
+            {
+                if patrol.chasing
+                {
+                    // Wait at the edge rather than follow the player off it.
+                    dir.x = 0.;
+                }
+                else
+                {
+                    dir.x = -dir.x;
+                }
+            }
+        }
+
+        if !patrol.chasing
+        {
+            patrol.dir = dir;
+        }
+        e_vel.0.x = dir.x*speed.x;
+        if flying
+        {
+            e_vel.0.y = dir.y*speed.y;
+        }
+        if dir.x != 0.
+        {
+            e_sprite.flip_x = dir.x < 0.;
+        }
+    }
+}
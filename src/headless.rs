@@ -0,0 +1,224 @@
+//! Running the game logic without a window or GPU.
+//!
+//! `LdtkPlugin` always adds the `bevy_ecs_tilemap` renderer, which needs a render device, so
+//! [HeadlessLdtkPlugin] registers the same LDtk assets, events and systems without it. Levels,
+//! entities and IntGrid cells are spawned exactly as in the game; tiles just never get drawn.
+//!
+//! [HeadlessApp] puts this together with `MinimalPlugins` and [PillarsPlugins], skips the title
+//! menu, loads `test_32.ldtk` from the `assets` folder and lets callers step the game and
+//! inspect the [World]. Its clock only moves by one physics tick per update, so runs are
+//! reproducible however fast the machine is. Saving is off unless
+//! [HeadlessApp::with_save_directory] turns it on, so runs neither read nor overwrite the
+//! player's saves.
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::{ButtonState, InputPlugin};
+use bevy::prelude::*;
+use bevy::render::texture::ImageTextureLoader;
+use bevy::time::TimeUpdateStrategy;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::{app, systems, LdtkLevelLoader, LdtkLoader, LdtkStage, LdtkSystemLabel};
+use iyes_loopless::prelude::*;
+
+use crate::{GameState, PillarsPlugins};
+use crate::physics::{PhysicsInterpolation, PHYSICS_DT};
+use crate::save::SaveSlots;
+use crate::streaming::WorldStreaming;
+
+/// The non-rendering half of `LdtkPlugin`.
+pub struct HeadlessLdtkPlugin;
+
+impl Plugin for HeadlessLdtkPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_stage_after(
+                CoreStage::Update,
+                LdtkStage::ProcessApi,
+                SystemStage::parallel(),
+            )
+            .init_non_send_resource::<app::LdtkEntityMap>()
+            .init_non_send_resource::<app::LdtkIntCellMap>()
+            .init_resource::<LdtkSettings>()
+            .init_resource::<ClearColor>()
+            .add_asset::<LdtkAsset>()
+            .init_asset_loader::<LdtkLoader>()
+            .add_asset::<LdtkLevel>()
+            .init_asset_loader::<LdtkLevelLoader>()
+            .add_event::<LevelEvent>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                systems::process_ldtk_assets.label(LdtkSystemLabel::ProcessAssets),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                systems::process_ldtk_levels.label(LdtkSystemLabel::LevelSpawning),
+            )
+            .add_system_to_stage(
+                LdtkStage::ProcessApi,
+                systems::worldly_adoption.label(LdtkSystemLabel::Other),
+            )
+            .add_system_to_stage(
+                LdtkStage::ProcessApi,
+                systems::apply_level_selection.label(LdtkSystemLabel::LevelSelection),
+            )
+            .add_system_to_stage(
+                LdtkStage::ProcessApi,
+                systems::apply_level_set
+                    .label(LdtkSystemLabel::LevelSet)
+                    .after(LdtkSystemLabel::LevelSelection),
+            )
+            .add_system_to_stage(
+                LdtkStage::ProcessApi,
+                systems::clean_respawn_entities.at_end(),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                systems::detect_level_spawned_events
+                    .pipe(systems::fire_level_transformed_events)
+                    .label(LdtkSystemLabel::Other),
+            );
+    }
+}
+
+/// A windowless [App] running [PillarsPlugins] from [GameState::Setup].
+pub struct HeadlessApp
+{
+    app: App,
+    now: Instant
+}
+
+impl HeadlessApp
+{
+    pub fn new() -> Self
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Image>()
+            .add_asset::<TextureAtlas>()
+            .init_asset_loader::<ImageTextureLoader>()
+            .add_plugin(HeadlessLdtkPlugin)
+            .add_loopless_state(GameState::Setup)
+            .add_plugins(PillarsPlugins)
+            // Nothing is drawn, so transforms should always hold physics positions.
+            .insert_resource(PhysicsInterpolation(false))
+            .insert_resource(SaveSlots { directory: None, active: 0 });
+
+        HeadlessApp { app, now: Instant::now() }
+    }
+
+    /// Starts in the level with the given LDtk identifier instead of `Yard`.
+    pub fn with_level(mut self, identifier: &str) -> Self
+    {
+        self.app.insert_resource(LevelSelection::Identifier(identifier.to_string()));
+        self
+    }
+
+    /// Lays levels out like in the LDtk world and streams them in, see [WorldStreaming].
+    pub fn with_world_streaming(mut self) -> Self
+    {
+        self.app.insert_resource(WorldStreaming(true));
+        self
+    }
+
+    /// Saves to `directory`, and loads from it on [LoadGame](crate::save::LoadGame), instead of
+    /// not saving at all.
+    pub fn with_save_directory(mut self, directory: impl Into<PathBuf>) -> Self
+    {
+        self.app.insert_resource(SaveSlots { directory: Some(directory.into()), active: 0 });
+        self
+    }
+
+    /// Updates until the level has loaded and the game is in [GameState::Gameplay].
+    ///
+    /// Returns `false` if that did not happen within `timeout`.
+    pub fn run_until_gameplay(&mut self, timeout: Duration) -> bool
+    {
+        let start = Instant::now();
+        while self.state() != Some(GameState::Gameplay)
+        {
+            if start.elapsed() > timeout
+            {
+                return false;
+            }
+            self.tick();
+            // Assets load on the IO task pool, give it a chance to finish.
+            thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
+    /// Runs `ticks` updates with exactly one physics tick each.
+    pub fn step(&mut self, ticks: usize)
+    {
+        for _ in 0..ticks
+        {
+            self.tick();
+        }
+    }
+
+    fn tick(&mut self)
+    {
+        self.now += Duration::from_secs_f32(PHYSICS_DT);
+        self.app.insert_resource(TimeUpdateStrategy::ManualInstant(self.now));
+        self.app.update();
+    }
+
+    /// Holds down `key` from the next tick on.
+    pub fn press(&mut self, key: KeyCode)
+    {
+        self.send_key(key, ButtonState::Pressed);
+    }
+
+    /// Releases `key` on the next tick.
+    pub fn release(&mut self, key: KeyCode)
+    {
+        self.send_key(key, ButtonState::Released);
+    }
+
+    fn send_key(&mut self, key: KeyCode, state: ButtonState)
+    {
+        self.app.world.resource_mut::<Events<KeyboardInput>>().send(KeyboardInput {
+            scan_code: 0,
+            key_code: Some(key),
+            state
+        });
+    }
+
+    /// The current [GameState], or `None` before the first update.
+    pub fn state(&self) -> Option<GameState>
+    {
+        self.app.world.get_resource::<CurrentState<GameState>>().map(|state| state.0)
+    }
+
+    pub fn world(&self) -> &World
+    {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World
+    {
+        &mut self.app.world
+    }
+
+    pub fn app_mut(&mut self) -> &mut App
+    {
+        &mut self.app
+    }
+}
+
+impl Default for HeadlessApp
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
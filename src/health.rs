@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{in_game, GameState};
+use crate::camera::CameraShake;
+use crate::death::{Dead, DeathCause, PlayerDied};
+use crate::input::PlayerId;
+use crate::level::ActiveLevel;
+use crate::ldtk_fields::{ldtk_fields, FromLdtkFields};
+use crate::physics::Vel;
+use crate::player::{player_color, Player, PlayerLabel};
+
+/// Hearts every player starts with.
+pub const PLAYER_MAX_HEALTH: u32 = 3;
+/// Seconds a player can't be hurt again after taking damage.
+pub const INVULNERABLE_TIME: f32 = 1.;
+/// Seconds of that in which the knockback takes over from the player's input.
+pub const KNOCKBACK_TIME: f32 = 0.2;
+/// Velocity a hit throws the player back with, away from what hit them.
+pub const KNOCKBACK: Vec2 = Vec2::new(360.,480.);
+/// How often the sprite blinks while invulnerable, in blinks per second.
+pub const FLASH_RATE: f32 = 12.;
+/// How the camera shakes when a player is hit.
+pub const HIT_SHAKE: CameraShake = CameraShake { strength: 6., duration: 0.25 };
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Health
+{
+    pub current: u32,
+    pub max: u32
+}
+
+impl Health
+{
+    pub fn new(max: u32) -> Health
+    {
+        Health { current: max, max }
+    }
+}
+
+/// Sent to hurt an entity with [Health]. Players who run out die from `source`.
+#[derive(Debug, Clone, Copy)]
+pub struct Damage
+{
+    pub entity: Entity,
+    pub amount: u32,
+    pub source: DeathCause,
+    /// Velocity the hit gives the entity, zero for none.
+    pub knockback: Vec2
+}
+
+impl Damage
+{
+    /// Damage that takes all health at once.
+    pub fn lethal(entity: Entity, source: DeathCause) -> Damage
+    {
+        Damage { entity, amount: u32::MAX, source, knockback: Vec2::ZERO }
+    }
+}
+
+/// A player who was just hurt and ignores further [Damage] for a moment.
+#[derive(Component)]
+pub struct Invulnerable
+{
+    pub timer: Timer
+}
+
+impl Invulnerable
+{
+    /// Whether the player is still being thrown back and can't steer.
+    pub fn knocked_back(&self) -> bool
+    {
+        self.timer.elapsed_secs() < KNOCKBACK_TIME
+    }
+}
+
+ldtk_fields! {
+    /// Rules for hazards in the current level, from its LDtk level fields.
+    #[derive(Resource, Default)]
+    pub struct LevelHazards
+    {
+        /// Spikes kill outright instead of taking one heart.
+        "OneHitSpikes" => pub one_hit_spikes: bool
+    }
+}
+
+#[derive(Component)]
+pub struct HeartsHud;
+
+/// Turns [Damage] into lost health, knockback and a short invulnerability.
+///
+/// Players at zero health send [PlayerDied]; anything else with [Health] is despawned. Each
+/// player's hearts are shown in the top left corner while [in_game].
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_event::<Damage>()
+            .init_resource::<LevelHazards>()
+            .add_system(read_level_hazards)
+            // After the movement input, so a knockback isn't overwritten before the player
+            // has turned invulnerable.
+            .add_system(apply_damage.run_in_state(GameState::Gameplay).after(PlayerLabel::Move))
+            .add_system(flash_invulnerable.run_in_state(GameState::Gameplay))
+            .add_system_to_stage(CoreStage::PostUpdate, draw_hearts.run_if(in_game));
+    }
+}
+
+pub fn read_level_hazards(
+    active_level: Res<ActiveLevel>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    mut hazards: ResMut<LevelHazards>)
+{
+    if !active_level.is_changed()
+    {
+        return;
+    }
+    if let Some(iid) = &active_level.iid
+    {
+        if let Some(level) = ldtk_levels.iter().map(|(_, ldtk_level)| &ldtk_level.level).find(|level| level.iid == *iid)
+        {
+            *hazards = LevelHazards::from_level(level);
+        }
+    }
+}
+
+pub fn apply_damage(
+    mut damage: EventReader<Damage>,
+    mut health_q: Query<(&mut Health, &mut Vel, Option<&PlayerId>, Option<&Invulnerable>), Without<Dead>>,
+    mut died: EventWriter<PlayerDied>,
+    mut shakes: EventWriter<CameraShake>,
+    mut commands: Commands)
+{
+    let mut hurt = Vec::new();
+    for hit in damage.iter()
+    {
+        // One hit per entity and frame, however many things touch it.
+        if hurt.contains(&hit.entity)
+        {
+            continue;
+        }
+        if let Ok((mut health, mut vel, player_id, invulnerable)) = health_q.get_mut(hit.entity)
+        {
+            if invulnerable.is_some()
+            {
+                continue;
+            }
+            hurt.push(hit.entity);
+            health.current = health.current.saturating_sub(hit.amount);
+            if player_id.is_some()
+            {
+                shakes.send(HIT_SHAKE);
+            }
+            match player_id
+            {
+                Some(player_id) if health.current == 0 =>
+                    died.send(PlayerDied { entity: hit.entity, player_id: *player_id, cause: hit.source }),
+                Some(_) =>
+                {
+                    if hit.knockback != Vec2::ZERO
+                    {
+                        vel.0 = hit.knockback;
+                    }
+                    commands.entity(hit.entity).insert(Invulnerable {
+                        timer: Timer::from_seconds(INVULNERABLE_TIME, TimerMode::Once)
+                    });
+                },
+                None if health.current == 0 => commands.entity(hit.entity).despawn_recursive(),
+                None => ()
+            }
+        }
+    }
+}
+
+pub fn flash_invulnerable(
+    mut invulnerable_q: Query<(&mut Invulnerable, &mut Sprite, Entity, Option<&Dead>)>,
+    time: Res<Time>,
+    mut commands: Commands)
+{
+    for (mut invulnerable, mut sprite, entity, dead) in invulnerable_q.iter_mut()
+    {
+        invulnerable.timer.tick(time.delta());
+        // Dying hands the sprite over to the death fade.
+        if invulnerable.timer.finished() || dead.is_some()
+        {
+            if dead.is_none()
+            {
+                sprite.color.set_a(1.);
+            }
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
+        }
+        let visible = ((invulnerable.timer.elapsed_secs()*FLASH_RATE) as u32).is_multiple_of(2);
+        sprite.color.set_a(if visible { 1. } else { 0.25 });
+    }
+}
+
+/// Rebuilds the hearts whenever a player's health changes or players come and go.
+pub fn draw_hearts(
+    player_q: Query<(&PlayerId, &Health), With<Player>>,
+    changed_q: Query<(), (With<Player>, Changed<Health>)>,
+    removed: RemovedComponents<Health>,
+    hud_q: Query<Entity, With<HeartsHud>>,
+    mut commands: Commands)
+{
+    let missing = hud_q.is_empty() && !player_q.is_empty();
+    if changed_q.is_empty() && removed.iter().next().is_none() && !missing
+    {
+        return;
+    }
+    for hud in hud_q.iter()
+    {
+        commands.entity(hud).despawn_recursive();
+    }
+
+    let mut players: Vec<(&PlayerId, &Health)> = player_q.iter().collect();
+    players.sort_by_key(|(p_id, _)| p_id.0);
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(16.), top: Val::Px(16.), ..default() },
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        ..default()
+    }, HeartsHud)).with_children(|hud| {
+        for (p_id, p_health) in players
+        {
+            hud.spawn(NodeBundle {
+                style: Style { margin: UiRect::bottom(Val::Px(6.)), ..default() },
+                ..default()
+            }).with_children(|row| {
+                for heart in 0..p_health.max
+                {
+                    let color = if heart < p_health.current { Color::rgb_u8(220, 40, 60) } else { Color::rgba_u8(60, 60, 60, 200) };
+                    row.spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(16.), Val::Px(16.)),
+                            margin: UiRect::right(Val::Px(4.)),
+                            border: UiRect::all(Val::Px(2.)),
+                            ..default()
+                        },
+                        background_color: player_color(*p_id).into(),
+                        ..default()
+                    }).with_children(|frame| {
+                        frame.spawn(NodeBundle {
+                            style: Style { size: Size::new(Val::Percent(100.), Val::Percent(100.)), ..default() },
+                            background_color: color.into(),
+                            ..default()
+                        });
+                    });
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,259 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::{Deserialize, Serialize};
+
+/// Asset the [InputBindings] are read from. Edits are picked up while the game runs.
+pub const INPUT_BINDINGS_PATH: &str = "default.bindings.ron";
+
+/// How far the left stick has to be pushed to count as a move.
+pub const STICK_DEADZONE: f32 = 0.5;
+
+/// Everything a player can do, independent of the key or button it is bound to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action
+{
+    MoveLeft,
+    MoveRight,
+    Jump,
+    /// Ground pound, with the earth totem.
+    Down,
+    /// Fire shot, with the fire totem.
+    Shoot,
+    /// Earth block, with the earth totem.
+    Build,
+    Interact,
+    Pause
+}
+
+impl Action
+{
+    /// Every action, in the order the settings screen lists them.
+    pub const ALL: [Action; 8] = [Action::MoveLeft, Action::MoveRight, Action::Jump, Action::Down, Action::Shoot, Action::Build, Action::Interact, Action::Pause];
+}
+
+/// Which player slot an entity is controlled by. Slot 0 is player one.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PlayerId(pub u8);
+
+/// Keys and gamepad buttons of one player slot.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PlayerBindings
+{
+    pub keys: HashMap<Action, Vec<KeyCode>>,
+    /// Id of the gamepad this player uses, if any. The left stick moves as well.
+    pub gamepad: Option<usize>,
+    pub buttons: HashMap<Action, Vec<GamepadButtonType>>
+}
+
+/// Bindings of every player slot, indexed by [PlayerId].
+#[derive(Resource, Serialize, Deserialize, TypeUuid, Debug, Clone, PartialEq)]
+#[uuid = "b3d7e1a4-6c2f-4e0b-8a95-1f4c7d2e9a63"]
+pub struct InputBindings
+{
+    pub players: Vec<PlayerBindings>
+}
+
+impl Default for InputBindings
+{
+    fn default() -> Self
+    {
+        InputBindings
+        {
+            players: vec![
+                PlayerBindings
+                {
+                    keys: HashMap::from_iter([
+                        (Action::MoveLeft, vec![KeyCode::A]),
+                        (Action::MoveRight, vec![KeyCode::D]),
+                        (Action::Jump, vec![KeyCode::Space]),
+                        (Action::Down, vec![KeyCode::S]),
+                        (Action::Shoot, vec![KeyCode::F]),
+                        (Action::Build, vec![KeyCode::Q]),
+                        (Action::Interact, vec![KeyCode::E]),
+                        (Action::Pause, vec![KeyCode::Escape])
+                    ]),
+                    gamepad: Some(0),
+                    buttons: default_buttons()
+                },
+                PlayerBindings
+                {
+                    keys: HashMap::from_iter([
+                        (Action::MoveLeft, vec![KeyCode::Numpad4]),
+                        (Action::MoveRight, vec![KeyCode::Numpad6]),
+                        (Action::Jump, vec![KeyCode::Numpad0]),
+                        (Action::Down, vec![KeyCode::Numpad2]),
+                        (Action::Shoot, vec![KeyCode::Numpad7]),
+                        (Action::Build, vec![KeyCode::Numpad9]),
+                        (Action::Interact, vec![KeyCode::Numpad5])
+                    ]),
+                    gamepad: Some(1),
+                    buttons: default_buttons()
+                }
+            ]
+        }
+    }
+}
+
+fn default_buttons() -> HashMap<Action, Vec<GamepadButtonType>>
+{
+    HashMap::from_iter([
+        (Action::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+        (Action::MoveRight, vec![GamepadButtonType::DPadRight]),
+        (Action::Jump, vec![GamepadButtonType::South]),
+        (Action::Down, vec![GamepadButtonType::DPadDown]),
+        (Action::Shoot, vec![GamepadButtonType::East]),
+        (Action::Build, vec![GamepadButtonType::North]),
+        (Action::Interact, vec![GamepadButtonType::West]),
+        (Action::Pause, vec![GamepadButtonType::Start])
+    ])
+}
+
+/// Current [Action]s of every player slot, updated before `Update` each frame.
+#[derive(Resource, Default)]
+pub struct PlayerInputs
+{
+    players: Vec<Input<Action>>,
+    /// While set, every action reads as released, e.g. while a door swaps levels.
+    pub locked: bool
+}
+
+impl PlayerInputs
+{
+    /// Actions of one player, or `None` if the slot has no bindings.
+    pub fn player(&self, id: PlayerId) -> Option<&Input<Action>>
+    {
+        self.players.get(id.0 as usize)
+    }
+
+    /// Whether any player pressed `action` this frame.
+    pub fn any_just_pressed(&self, action: Action) -> bool
+    {
+        self.players.iter().any(|input| input.just_pressed(action))
+    }
+}
+
+#[derive(Default)]
+pub struct InputBindingsLoader;
+
+impl AssetLoader for InputBindingsLoader
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let bindings: InputBindings = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(bindings));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["bindings.ron"]
+    }
+}
+
+/// Handle keeping the loaded [InputBindings] asset alive.
+#[derive(Resource)]
+pub struct InputBindingsHandle(pub Handle<InputBindings>);
+
+/// Maps keyboard and gamepad input to per-player [Action]s.
+///
+/// Gameplay systems read [PlayerInputs] with a [PlayerId] instead of raw keys, so the
+/// bindings can be changed in [INPUT_BINDINGS_PATH] or by replacing the [InputBindings].
+pub struct ActionInputPlugin;
+
+impl Plugin for ActionInputPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_asset::<InputBindings>()
+            .init_asset_loader::<InputBindingsLoader>()
+            .init_resource::<InputBindings>()
+            .init_resource::<PlayerInputs>()
+            .add_startup_system(load_input_bindings)
+            .add_system(apply_input_bindings)
+            .add_system_to_stage(CoreStage::PreUpdate, update_player_inputs.after(InputSystem));
+    }
+}
+
+pub fn load_input_bindings(asset_server: Res<AssetServer>, mut commands: Commands)
+{
+    commands.insert_resource(InputBindingsHandle(asset_server.load(INPUT_BINDINGS_PATH)));
+}
+
+pub fn apply_input_bindings(
+    mut events: EventReader<AssetEvent<InputBindings>>,
+    handle: Option<Res<InputBindingsHandle>>,
+    assets: Res<Assets<InputBindings>>,
+    mut bindings: ResMut<InputBindings>)
+{
+    if let Some(handle) = handle
+    {
+        for event in events.iter()
+        {
+            match event
+            {
+                AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed } if *changed == handle.0 =>
+                {
+                    if let Some(loaded) = assets.get(changed)
+                    {
+                        *bindings = loaded.clone();
+                    }
+                },
+                _ => ()
+            }
+        }
+    }
+}
+
+pub fn update_player_inputs(
+    bindings: Res<InputBindings>,
+    kb: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut inputs: ResMut<PlayerInputs>)
+{
+    inputs.players.resize_with(bindings.players.len(), Input::default);
+    if inputs.locked
+    {
+        inputs.players.iter_mut().for_each(Input::reset_all);
+        return;
+    }
+    for (player, input) in bindings.players.iter().zip(inputs.players.iter_mut())
+    {
+        input.clear();
+        let stick = |axis| player.gamepad
+            .and_then(|id| axes.get(GamepadAxis::new(Gamepad::new(id), axis)))
+            .unwrap_or(0.);
+        let stick_x = stick(GamepadAxisType::LeftStickX);
+        let stick_y = stick(GamepadAxisType::LeftStickY);
+        for action in Action::ALL
+        {
+            let key_held = player.keys.get(&action)
+                .is_some_and(|keys| kb.any_pressed(keys.iter().copied()));
+            let button_held = player.gamepad.zip(player.buttons.get(&action))
+                .is_some_and(|(id, types)| buttons.any_pressed(types.iter().map(|t| GamepadButton::new(Gamepad::new(id), *t))));
+            let stick_held = match action
+            {
+                Action::MoveLeft => stick_x <= -STICK_DEADZONE,
+                Action::MoveRight => stick_x >= STICK_DEADZONE,
+                Action::Down => stick_y <= -STICK_DEADZONE,
+                _ => false
+            };
+            if key_held || button_held || stick_held
+            {
+                input.press(action);
+            }
+            else
+            {
+                input.release(action);
+            }
+        }
+    }
+}
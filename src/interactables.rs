@@ -0,0 +1,672 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::sprite::collide_aabb::collide;
+use bevy_ecs_ldtk::ldtk::FieldInstanceEntityReference;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::abilities::Abilities;
+use crate::animation::{AnimationClip, SpriteAnimation};
+use crate::death::{Dead, DeathCause};
+use crate::health::{Damage, Health, LevelHazards, KNOCKBACK};
+use crate::input::PlayerId;
+use crate::ldtk_fields::{ldtk_fields, FromLdtkFields};
+use crate::loading::{AIR_TOTEM_TEXTURE, EARTH_TOTEM_TEXTURE, FIRE_TOTEM_TEXTURE, NPC_TEXTURE, SLIME_TEXTURE, WATER_TOTEM_TEXTURE};
+use crate::movement::{MovementConfig, MovementOverride};
+use crate::physics::{Actor, Vel, PHYSICS_DT};
+use crate::player::Player;
+use crate::score::CoinCollected;
+use crate::triggers::GameFlags;
+
+#[derive(Default)]
+pub enum NpcType{
+    Alpaca,
+    #[default]
+    Test
+}
+#[derive(Default)]
+pub enum TotemType{
+    Fire,
+    Earth,
+    Water,
+    #[default]
+    Air
+}
+#[derive(Default)]
+pub enum Direction{
+    #[default]
+    North,
+    South,
+    East,
+    West
+}
+
+#[derive(Component, Default)]
+pub struct Checkpoint
+{
+    pub id: u8,
+    pub lit: bool,
+    /// Uid of the LDtk level the checkpoint is in.
+    pub level_uid: i32
+}
+#[derive(Bundle, Default)]
+pub struct CheckpointBundle{
+    pub checkpoint: Checkpoint,
+    pub sprite_bundle: SpriteBundle
+}
+pub const CHECKPOINT_UNLIT: Color = Color::rgb(0.35, 0.35, 0.35);
+pub const CHECKPOINT_LIT: Color = Color::rgb(1., 0.67, 0.16);
+
+/// The last [Checkpoint] lit in each level, kept while levels come and go.
+#[derive(Resource, Default)]
+pub struct LitCheckpoints
+{
+    /// Checkpoint id by level uid.
+    pub per_level: HashMap<i32, u8>,
+    /// Level uid and id of the checkpoint lit most recently. Saved games resume here.
+    pub latest: Option<(i32, u8)>
+}
+
+impl LitCheckpoints
+{
+    /// Whether `checkpoint` is the one players in its level respawn at.
+    pub fn is_respawn_point(&self, checkpoint: &Checkpoint) -> bool
+    {
+        self.per_level.get(&checkpoint.level_uid) == Some(&checkpoint.id)
+    }
+}
+/// A collectible coin, remembered by its LDtk entity iid once picked up.
+#[derive(Component, Default)]
+pub struct Coin
+{
+    pub iid: String
+}
+
+impl From<EntityInstance> for Coin
+{
+    fn from(entity_instance: EntityInstance) -> Coin
+    {
+        Coin { iid: entity_instance.iid }
+    }
+}
+#[derive(Component, Default)]
+pub struct Door{
+    /// Identifier of the level the door leads to.
+    pub id: String,
+    /// Locked doors ignore the player until a trigger opens them.
+    pub open: bool,
+    /// LDtk iid of the door itself.
+    pub iid: String,
+    /// `ID` other doors lead to this one by.
+    pub door_id: Option<i32>,
+    pub target: DoorTarget
+}
+/// Where players come out after going through a [Door].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DoorTarget
+{
+    /// At the level's `PlayerSpawn`.
+    #[default]
+    PlayerSpawn,
+    /// At the door an LDtk entity reference points to, wherever its level is.
+    Door { level_iid: String, entity_iid: String },
+    /// At the door with this `ID` in the level named by the door's `LvID`.
+    Id(i32)
+}
+impl Door
+{
+    /// The level to switch to, or `None` if the door leads nowhere.
+    pub fn destination(&self) -> Option<LevelSelection>
+    {
+        match &self.target
+        {
+            DoorTarget::Door { level_iid, .. } => Some(LevelSelection::Iid(level_iid.to_owned())),
+            _ if self.id.is_empty() => None,
+            _ => Some(LevelSelection::Identifier(self.id.to_owned()))
+        }
+    }
+}
+#[derive(Component, Default)]
+pub struct Npc
+{
+    pub npc_type: NpcType,
+    /// Name of the conversation in the dialogue file.
+    pub dialogue: String
+}
+#[derive(Bundle)]
+pub struct NpcBundle
+{
+    pub npc: Npc,
+    pub sprite_bundle: SpriteBundle
+}
+#[derive(Component, Default)]
+pub struct Enemy
+{
+    /// How far it patrols from where it spawned, in tiles. 0 means until it is stopped.
+    pub move_distance: Vec2,
+    /// Tiles per second.
+    pub move_speed: Vec2,
+    pub tangible: bool,
+    pub smart: bool
+}
+#[derive(Component, Default)]
+pub struct Killer(pub Direction);
+#[derive(Bundle)]
+pub struct KillerBundle{
+    pub killer: Killer,
+    pub transform: Transform
+}
+
+#[derive(Bundle)]
+pub struct EnemyBundle
+{
+    pub enemy: Enemy,
+    pub vel: Vel,
+    pub actor: Actor,
+    pub health: Health,
+    pub movement: MovementOverride,
+    //#[sprite_bundle("monochrome_tilemap_transparent_packed.png")]
+    pub sprite_bundle: SpriteBundle
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct CoinBundle
+{
+    #[from_entity_instance]
+    pub coin: Coin,
+    pub vel: Vel,
+    #[sprite_sheet_bundle(
+        "monochrome_tilemap_transparent_packed.png",
+        16.,
+        16.,
+        3,
+        1,
+        0.,
+        0.,
+        0
+    )]
+    #[bundle]
+    pub sprite_bundle: SpriteSheetBundle,
+    #[with(coin_animation)]
+    pub animation: SpriteAnimation,
+    /*#[sprite_bundle("ghost.png")]
+    #[bundle]
+    pub sprite_bundle: SpriteBundle*/
+}
+
+#[derive(Bundle)]
+pub struct DoorBundle
+{
+    pub door: Door,
+    pub sprite: SpriteBundle
+}
+
+#[derive(Component, Default)]
+pub struct Totem(pub TotemType);
+
+#[derive(Bundle)]
+pub struct TotemBundle
+{
+    pub sprite_bundle: SpriteBundle,
+    pub totem: Totem
+}
+
+ldtk_fields! {
+    struct DoorFields
+    {
+        /// Identifier of the level the door leads to.
+        "LvID" => level: Option<String>,
+        "Locked" => locked: bool,
+        "ID" => id: Option<i32>,
+        /// The door to come out at; `TargetID` names one in the `LvID` level instead.
+        "Target" => target: Option<FieldInstanceEntityReference>,
+        "TargetID" => target_id: Option<i32>
+    }
+}
+
+ldtk_fields! {
+    struct CheckpointFields
+    {
+        "ID" => id: u8
+    }
+}
+
+ldtk_fields! {
+    struct NpcFields
+    {
+        /// Name of the `Npc` enum value.
+        "Npc" => kind: String,
+        "Dialogue" => dialogue: Option<String>
+    }
+}
+
+ldtk_fields! {
+    struct TotemFields
+    {
+        /// Name of the `Totem` enum value.
+        "Totem" => totem: String
+    }
+}
+
+ldtk_fields! {
+    struct EnemyFields
+    {
+        "MoveSpeedX" => speed_x: Option<f32>,
+        "MoveSpeedY" => speed_y: Option<f32>,
+        "MoveDistanceX" => distance_x: Option<f32>,
+        "MoveDistanceY" => distance_y: Option<f32>,
+        "Tangible" => tangible: bool = true,
+        "Smart" => smart: bool = true
+    }
+}
+
+impl LdtkEntity for DoorBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        _: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> DoorBundle {
+        let sprite = Sprite {
+            custom_size: Some(Vec2::new(entity_instance.width as f32,entity_instance.height as f32)),
+            color: Color::Rgba { red: 0., green: 0., blue: 0., alpha: 0. },
+            ..Default::default()
+        };
+
+        let fields = DoorFields::from_entity(entity_instance, layer_instance);
+        let target = match (fields.target, fields.target_id)
+        {
+            (Some(target), _) => DoorTarget::Door { level_iid: target.level_iid, entity_iid: target.entity_iid },
+            (None, Some(id)) => DoorTarget::Id(id),
+            (None, None) => DoorTarget::PlayerSpawn
+        };
+        if fields.level.is_none() && !matches!(target, DoorTarget::Door { .. })
+        {
+            warn!("Door {} in level {} has neither an LvID nor a Target", entity_instance.iid, layer_instance.level_id);
+        }
+
+        DoorBundle {
+            door: Door{
+                id: fields.level.unwrap_or_default(),
+                open: !fields.locked,
+                iid: entity_instance.iid.to_owned(),
+                door_id: fields.id,
+                target
+            },
+            sprite: SpriteBundle{sprite,..default()}
+        }
+    }
+}
+
+impl LdtkEntity for CheckpointBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        _: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> CheckpointBundle {
+
+        CheckpointBundle
+        {
+            checkpoint: Checkpoint { id: CheckpointFields::from_entity(entity_instance, layer_instance).id, lit: false, level_uid: layer_instance.level_id },
+            sprite_bundle: SpriteBundle { sprite: Sprite{color: CHECKPOINT_UNLIT, custom_size: Some(Vec2::new(16.,32.)),..default()}, ..default() }
+        }
+    }
+}
+
+impl LdtkEntity for NpcBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        asset_server: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> NpcBundle {
+
+        let fields = NpcFields::from_entity(entity_instance, layer_instance);
+        let npc_type = if fields.kind.to_lowercase() == "alpaca" { NpcType::Alpaca } else { NpcType::default() };
+
+        // NPCs without a conversation of their own use the one named after their kind.
+        let dialogue = fields.dialogue.unwrap_or_else(|| match npc_type
+        {
+            NpcType::Alpaca => "alpaca".to_string(),
+            NpcType::Test => "test".to_string()
+        });
+
+        NpcBundle
+        {
+            npc: Npc { npc_type, dialogue },
+            sprite_bundle: SpriteBundle { texture: asset_server.load(NPC_TEXTURE), sprite: Sprite { color: Color::rgb_u8(148, 170, 217), custom_size: Some(Vec2::new(16.,32.)), ..default()}, ..default()}
+        }
+    }
+}
+
+impl LdtkEntity for TotemBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        asset_server: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> TotemBundle {
+
+        let mut totem_type = TotemType::default();
+        let mut totem_sprite: Handle<Image> = asset_server.load(AIR_TOTEM_TEXTURE);
+
+        match TotemFields::from_entity(entity_instance, layer_instance).totem.to_lowercase().as_str()
+        {
+            "firetotem" => {
+                totem_sprite = asset_server.load(FIRE_TOTEM_TEXTURE);
+                totem_type = TotemType::Fire;
+            },
+            "earthtotem" => {
+                totem_sprite = asset_server.load(EARTH_TOTEM_TEXTURE);
+                totem_type = TotemType::Earth;
+            },
+            "watertotem" => {
+                totem_sprite = asset_server.load(WATER_TOTEM_TEXTURE);
+                totem_type = TotemType::Water;
+            },
+            _ => ()
+        }
+
+        TotemBundle
+        {
+            sprite_bundle: SpriteBundle { texture: totem_sprite , ..default()},
+            totem: Totem(totem_type)
+        }
+    }
+}
+
+impl LdtkEntity for EnemyBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        asset_server: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> EnemyBundle {
+
+        let fields = EnemyFields::from_entity(entity_instance, layer_instance);
+        let speed = Vec2::new(fields.speed_x.unwrap_or(0.), fields.speed_y.unwrap_or(0.));
+        let distance = Vec2::new(fields.distance_x.unwrap_or(0.), fields.distance_y.unwrap_or(0.));
+
+        // Enemies moving up and down fly, unless their gravity is set explicitly.
+        let mut movement = MovementOverride::from_entity(entity_instance, layer_instance);
+        if speed.y != 0. && movement.gravity.is_none()
+        {
+            movement.gravity = Some(0.);
+        }
+
+        EnemyBundle::new(
+            Enemy { move_distance: distance, move_speed: speed, tangible: fields.tangible, smart: fields.smart },
+            movement,
+            asset_server
+        )
+    }
+}
+
+impl EnemyBundle
+{
+    pub fn new(enemy: Enemy, movement: MovementOverride, asset_server: &AssetServer) -> EnemyBundle
+    {
+        EnemyBundle
+        {
+            sprite_bundle: SpriteBundle { texture: asset_server.load(SLIME_TEXTURE), sprite: Sprite { color: Color::rgb_u8(255, 0, 0), custom_size: Some(Vec2::new(32.,32.)),..default()},..default()},
+            enemy,
+            vel: Vel(Vec2::new(0.,0.)),
+            actor: Actor { grounded: false, jumped: false, coyote_time: 0., jump_count: 0, jump_limit: 0 },
+            health: Health::new(1),
+            movement
+        }
+    }
+}
+
+impl LdtkIntCell for KillerBundle
+{
+    fn bundle_int_cell(int_grid_cell: IntGridCell, _: &LayerInstance) -> Self {
+        match int_grid_cell.value
+        {
+            2 => KillerBundle { killer: Killer(Direction::North), transform: Transform::default() },
+            3 => KillerBundle { killer: Killer(Direction::South), transform: Transform::default() },
+            4 => KillerBundle { killer: Killer(Direction::East), transform: Transform::default() },
+            5 => KillerBundle { killer: Killer(Direction::West), transform: Transform::default() },
+            _ => KillerBundle { killer: Killer(Direction::North), transform: Transform::default() }
+        }
+    }
+}
+
+/// Everything the player can touch: checkpoints, spikes, coins, totems, NPCs and enemies.
+pub struct InteractablesPlugin;
+
+impl Plugin for InteractablesPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.register_ldtk_int_cell_for_layer::<KillerBundle>("IntGrid",2)
+            .register_ldtk_int_cell_for_layer::<KillerBundle>("IntGrid",3)
+            .register_ldtk_int_cell_for_layer::<KillerBundle>("IntGrid",4)
+            .register_ldtk_int_cell_for_layer::<KillerBundle>("IntGrid",5)
+            .register_ldtk_entity::<CoinBundle>("Dubloon")
+            .register_ldtk_entity::<DoorBundle>("Door")
+            .register_ldtk_entity::<TotemBundle>("Totem")
+            .register_ldtk_entity::<EnemyBundle>("Enemy")
+            .register_ldtk_entity::<CheckpointBundle>("Checkpoint")
+            .register_ldtk_entity::<NpcBundle>("Npc")
+            .init_resource::<LitCheckpoints>()
+            .add_system(restore_checkpoints)
+            .add_system(checkpoint.run_in_state(GameState::Gameplay))
+            .add_system(coin.run_in_state(GameState::Gameplay))
+            .add_system(killer.run_in_state(GameState::Gameplay))
+            .add_system(totem.run_in_state(GameState::Gameplay))
+            .add_system(enemy_react.run_in_state(GameState::Gameplay));
+    }
+}
+
+pub fn checkpoint(player_q: Query<&Transform, (With<Player>, Without<Dead>)>, mut checkpoint_q: Query<(&Transform, &mut Checkpoint, &mut Sprite), Without<Player>>, mut lit_checkpoints: ResMut<LitCheckpoints>)
+{
+    for p_transform in player_q.iter()
+    {
+        for (c_transform, mut c_checkpoint, mut c_sprite) in checkpoint_q.iter_mut()
+        {
+            if collide(
+                p_transform.translation,
+               Vec2::new(16.,32.),
+                c_transform.translation,
+              c_sprite.custom_size.unwrap()
+            ).is_some() && !lit_checkpoints.is_respawn_point(&c_checkpoint)
+            {
+                c_checkpoint.lit = true;
+                c_sprite.color = CHECKPOINT_LIT;
+                lit_checkpoints.per_level.insert(c_checkpoint.level_uid, c_checkpoint.id);
+                lit_checkpoints.latest = Some((c_checkpoint.level_uid, c_checkpoint.id));
+            }
+        }
+    }
+}
+
+/// Lights checkpoints again when their level is spawned anew.
+pub fn restore_checkpoints(mut checkpoint_q: Query<(&mut Checkpoint, &mut Sprite), Added<Checkpoint>>, lit_checkpoints: Res<LitCheckpoints>)
+{
+    for (mut c_checkpoint, mut c_sprite) in checkpoint_q.iter_mut()
+    {
+        if lit_checkpoints.is_respawn_point(&c_checkpoint)
+        {
+            c_checkpoint.lit = true;
+            c_sprite.color = CHECKPOINT_LIT;
+        }
+    }
+}
+
+pub fn killer(player_q: Query<(&Transform, &Vel, Entity), (With<Player>, Without<Dead>)>,killer_q: Query<(&Transform, &Killer, &Parent),(With<Killer>,Without<Player>)>, layer_q: Query<&Transform, Without<Killer>>, hazards: Res<LevelHazards>, mut damage: EventWriter<Damage>)
+{
+    for (p_transform, p_vel, p_entity) in player_q.iter()
+    {
+        for (k_transform, k_stats, k_parent) in killer_q.iter()
+        {
+            // Killer tiles sit in their layer, which is where the level is in the world.
+            let k_translation = k_transform.translation+layer_q.get(k_parent.get()).map_or(Vec3::ZERO, |l_transform| l_transform.translation.truncate().extend(0.));
+            let mut k_size = Vec2::new(32.,14.);
+            let mut k_delta = Vec2::new(16.,7.);
+            match k_stats.0
+            {
+                Direction::East => {
+                    k_size = Vec2::new(14.,32.);
+                    k_delta = Vec2::new(7.,16.);
+                },
+                Direction::West => {
+                    k_size = Vec2::new(14.,32.);
+                    k_delta = Vec2::new(21.,16.);
+                },
+                Direction::North => (),
+                Direction::South => {
+                    k_delta = Vec2::new(16.,21.);
+                },
+            }
+            if collide(
+                p_transform.translation+(p_vel.0*PHYSICS_DT).extend(0.),
+                Vec2::new(16.,32.),
+                k_translation+k_delta.extend(0.),
+                k_size
+            ).is_some()
+            {
+                if hazards.one_hit_spikes
+                {
+                    damage.send(Damage::lethal(p_entity, DeathCause::Spikes));
+                    continue;
+                }
+                // Away from the spikes, and never up into a ceiling.
+                let away = (p_transform.translation.x-(k_translation.x+k_delta.x)).signum();
+                let up = if let Direction::South = k_stats.0 { 0. } else { KNOCKBACK.y };
+                damage.send(Damage {
+                    entity: p_entity,
+                    amount: 1,
+                    source: DeathCause::Spikes,
+                    knockback: Vec2::new(away*KNOCKBACK.x, up)
+                });
+            }
+        }
+    }
+}
+
+/// Spins through the three frames of the coin sheet.
+fn coin_animation(_: EntityInstance) -> SpriteAnimation
+{
+    SpriteAnimation { clip: AnimationClip { frames: vec![0, 1, 2], fps: 8., looping: true }, time: 0. }
+}
+
+pub fn coin(
+    player_q: Query<(&Transform, &PlayerId), (With<Player>, Without<Dead>)>,
+    coin_q: Query<(&Transform, &Coin, Entity, Option<&Parent>),Without<Player>>,
+    level_q: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    mut collected: EventWriter<CoinCollected>,
+    mut commands: Commands)
+{
+    let mut taken = Vec::new();
+    for (p_transform, p_id) in player_q.iter()
+    {
+        for (c_transform, c_coin, c_entity, c_parent) in coin_q.iter()
+        {
+            if !taken.contains(&c_entity) && collide(
+                p_transform.translation,
+               Vec2::new(16.,32.),
+                c_transform.translation,
+                Vec2::splat(16.)
+            ).is_some()
+            {
+                let level_iid = c_parent
+                    .and_then(|parent| level_q.get(parent.get()).ok())
+                    .and_then(|handle| ldtk_levels.get(handle))
+                    .map(|ldtk_level| ldtk_level.level.iid.to_owned())
+                    .unwrap_or_default();
+                collected.send(CoinCollected { iid: c_coin.iid.to_owned(), level_iid, player_id: *p_id });
+                commands.entity(c_entity).despawn();
+                taken.push(c_entity);
+            }
+        }
+    }
+}
+
+//fn after_death(player_q: Query<&mut Transform>)
+
+/// Hands out the power of a totem to the player who touches it.
+pub fn totem(mut player_q: Query<(&Transform, &mut Actor, &mut Abilities, &Vel), (With<Player>, Without<Dead>)>,totem_q: Query<(&Transform, &Totem, Entity), (Without<Player>, With<Totem>)>, mut flags: ResMut<GameFlags>, mut commands: Commands)
+{
+    for (p_transform, mut p_actor, mut p_abilities, p_vel) in player_q.iter_mut()
+    {
+        for (t_transform, t_type, t_entity) in totem_q.iter()
+        {
+            if collide(
+                p_transform.translation+(p_vel.0*PHYSICS_DT).extend(0.),
+               Vec2::new(16.,32.),
+                t_transform.translation,
+                Vec2::splat(32.)
+            ).is_some()
+            {
+                match t_type.0
+                {
+                    TotemType::Fire => p_abilities.fire = true,
+                    TotemType::Earth => p_abilities.earth = true,
+                    TotemType::Water => p_abilities.water = true,
+                    TotemType::Air =>
+                    {
+                        if !p_abilities.air
+                        {
+                            p_actor.jump_limit += 1;
+                        }
+                        p_abilities.air = true;
+                    }
+                }
+                flags.0.insert("got_totem".to_string());
+                commands.entity(t_entity).despawn();
+            }
+        }
+    }
+}
+
+pub fn enemy_react(mut player_q: Query<(&Transform, &mut Actor, &mut Vel, Entity, Option<&MovementOverride>), (With<Player>, Without<Dead>)>,enemy_q: Query<(&Transform, &Enemy, Entity), (Without<Player>, With<Enemy>)>, movement: Res<MovementConfig>, mut damage: EventWriter<Damage>)
+{
+    for (p_transform, mut p_actor, mut p_vel, p_entity, p_overrides) in player_q.iter_mut()
+    {
+        for (e_transform, e_stats, e_entity) in enemy_q.iter()
+        {
+            let knockback = Vec2::new((p_transform.translation.x-e_transform.translation.x).signum()*KNOCKBACK.x, KNOCKBACK.y);
+            if collide(
+                p_transform.translation+Vec3::new(0.,p_vel.0.y*PHYSICS_DT,0.),
+               Vec2::new(16.,32.),
+                e_transform.translation,
+                Vec2::splat(32.)
+            ).is_some()
+            {
+                if p_vel.0.y < 0. && p_transform.translation.y-16. >= e_transform.translation.y+16. && e_stats.tangible
+                {
+                    damage.send(Damage::lethal(e_entity, DeathCause::Squashed));
+                    p_vel.0.y = movement.with(p_overrides).jump_impulse;
+                    p_actor.jumped = false;
+                    p_actor.jump_count += p_actor.jump_limit-1;
+                }
+                else {
+                    damage.send(Damage { entity: p_entity, amount: 1, source: DeathCause::Enemy, knockback });
+                }
+            }
+            if collide(
+                p_transform.translation+Vec3::new(p_vel.0.x*PHYSICS_DT,0.,0.),
+               Vec2::new(16.,32.),
+                e_transform.translation,
+                Vec2::splat(32.)
+            ).is_some()
+            {
+                damage.send(Damage { entity: p_entity, amount: 1, source: DeathCause::Enemy, knockback });
+            }
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::{FieldInstance, FieldInstanceEntityReference, Level};
+use bevy_ecs_ldtk::prelude::*;
+
+/// A type an LDtk field value can be read as.
+pub trait LdtkFieldValue: Sized
+{
+    /// LDtk type the field should have, for warnings.
+    const EXPECTED: &'static str;
+
+    /// `Some(None)` if the field is blank, `None` if it holds a value of another type.
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>;
+}
+
+impl LdtkFieldValue for bool
+{
+    const EXPECTED: &'static str = "a Bool";
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        match value
+        {
+            FieldValue::Bool(value) => Some(Some(*value)),
+            _ => None
+        }
+    }
+}
+
+impl LdtkFieldValue for i32
+{
+    const EXPECTED: &'static str = "an Int";
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        match value
+        {
+            FieldValue::Int(value) => Some(*value),
+            _ => None
+        }
+    }
+}
+
+impl LdtkFieldValue for u32
+{
+    const EXPECTED: &'static str = "an Int of 0 or more";
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        match value
+        {
+            FieldValue::Int(Some(value)) => u32::try_from(*value).ok().map(Some),
+            FieldValue::Int(None) => Some(None),
+            _ => None
+        }
+    }
+}
+
+impl LdtkFieldValue for u8
+{
+    const EXPECTED: &'static str = "an Int from 0 to 255";
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        match value
+        {
+            FieldValue::Int(Some(value)) => u8::try_from(*value).ok().map(Some),
+            FieldValue::Int(None) => Some(None),
+            _ => None
+        }
+    }
+}
+
+impl LdtkFieldValue for f32
+{
+    const EXPECTED: &'static str = "a Float";
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        match value
+        {
+            FieldValue::Float(value) => Some(*value),
+            _ => None
+        }
+    }
+}
+
+/// Strings, multilines, file paths and enum values, which LDtk gives by name.
+impl LdtkFieldValue for String
+{
+    const EXPECTED: &'static str = "a String or Enum";
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        match value
+        {
+            FieldValue::String(value) | FieldValue::FilePath(value) | FieldValue::Enum(value) => Some(value.to_owned()),
+            _ => None
+        }
+    }
+}
+
+/// Arrays of strings, with their blank entries left out.
+impl LdtkFieldValue for Vec<String>
+{
+    const EXPECTED: &'static str = "an Array<String>";
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        match value
+        {
+            FieldValue::Strings(values) | FieldValue::FilePaths(values) | FieldValue::Enums(values) =>
+                Some(Some(values.iter().flatten().cloned().collect())),
+            _ => None
+        }
+    }
+}
+
+impl LdtkFieldValue for FieldInstanceEntityReference
+{
+    const EXPECTED: &'static str = "an EntityRef";
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        match value
+        {
+            FieldValue::EntityRef(value) => Some(value.to_owned()),
+            _ => None
+        }
+    }
+}
+
+/// Fields that may be left blank on purpose, which reads as `None` without a warning.
+impl<T: LdtkFieldValue> LdtkFieldValue for Option<T>
+{
+    const EXPECTED: &'static str = T::EXPECTED;
+
+    fn from_field(value: &FieldValue) -> Option<Option<Self>>
+    {
+        T::from_field(value).map(Some)
+    }
+}
+
+/// The fields of an LDtk entity or level, with what they belong to for warnings.
+pub struct LdtkFields<'a>
+{
+    fields: &'a [FieldInstance],
+    owner: String
+}
+
+impl<'a> LdtkFields<'a>
+{
+    pub fn of_entity(entity_instance: &'a EntityInstance, layer_instance: &LayerInstance) -> LdtkFields<'a>
+    {
+        LdtkFields
+        {
+            fields: &entity_instance.field_instances,
+            owner: format!("{} {} in level {}", entity_instance.identifier, entity_instance.iid, layer_instance.level_id)
+        }
+    }
+
+    pub fn of_level(level: &'a Level) -> LdtkFields<'a>
+    {
+        LdtkFields { fields: &level.field_instances, owner: format!("level {}", level.identifier) }
+    }
+
+    /// The value of the field called `name`.
+    ///
+    /// `None` if there is no such field, which is how optional fields are left out of the
+    /// project. A blank field or one of another type is `None` too, with a warning.
+    pub fn get<T: LdtkFieldValue>(&self, name: &str) -> Option<T>
+    {
+        match self.read(name)
+        {
+            Ok(value) => value,
+            Err(warning) =>
+            {
+                warn!("{}", warning);
+                None
+            }
+        }
+    }
+
+    /// Like [LdtkFields::get], but returns the warning instead of logging it.
+    pub fn read<T: LdtkFieldValue>(&self, name: &str) -> Result<Option<T>, String>
+    {
+        let field_instance = match self.fields.iter().find(|f| f.identifier == *name)
+        {
+            Some(field_instance) => field_instance,
+            None => return Ok(None)
+        };
+        match T::from_field(&field_instance.value)
+        {
+            Some(Some(value)) => Ok(Some(value)),
+            Some(None) => Err(format!("Field {} of {} is blank, using the default", name, self.owner)),
+            None => Err(format!("Field {} of {} should be {} but is {:?}, using the default",
+                name, self.owner, T::EXPECTED, field_instance.value))
+        }
+    }
+}
+
+/// Something read from the fields of an LDtk entity or level, usually declared with [ldtk_fields].
+pub trait FromLdtkFields: Sized
+{
+    fn from_ldtk_fields(fields: &LdtkFields) -> Self;
+
+    fn from_entity(entity_instance: &EntityInstance, layer_instance: &LayerInstance) -> Self
+    {
+        Self::from_ldtk_fields(&LdtkFields::of_entity(entity_instance, layer_instance))
+    }
+
+    fn from_level(level: &Level) -> Self
+    {
+        Self::from_ldtk_fields(&LdtkFields::of_level(level))
+    }
+}
+
+/// Declares a struct and its [FromLdtkFields] impl, one member per LDtk field:
+///
+/// ```ignore
+/// ldtk_fields! {
+///     struct DoorFields
+///     {
+///         "LvID" => level: String,
+///         "Locked" => locked: bool = false
+///     }
+/// }
+/// ```
+///
+/// Members take their default, or `Default::default()` when none is given, if the field is
+/// missing, blank or of the wrong type. `Option` members may be left blank in LDtk.
+macro_rules! ldtk_fields
+{
+    (@default) => { Default::default() };
+    (@default $default:expr) => { $default };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident
+        {
+            $(
+                $(#[$member_meta:meta])*
+                $field:literal => $member_vis:vis $member:ident: $ty:ty $(= $default:expr)?
+            ),* $(,)?
+        }
+    ) =>
+    {
+        $(#[$meta])*
+        $vis struct $name
+        {
+            $(
+                $(#[$member_meta])*
+                $member_vis $member: $ty
+            ),*
+        }
+
+        impl $crate::ldtk_fields::FromLdtkFields for $name
+        {
+            fn from_ldtk_fields(fields: &$crate::ldtk_fields::LdtkFields) -> Self
+            {
+                $name
+                {
+                    $(
+                        $member: fields.get($field)
+                            .unwrap_or_else(|| $crate::ldtk_fields::ldtk_fields!(@default $($default)?))
+                    ),*
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use ldtk_fields;
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    ldtk_fields! {
+        #[derive(Debug, PartialEq)]
+        struct TestFields
+        {
+            "Locked" => locked: bool,
+            "Speed" => speed: f32 = 2.,
+            "Count" => count: u8 = 7,
+            "Target" => target: Option<i32>,
+            "Actions" => actions: Vec<String>
+        }
+    }
+
+    fn field(identifier: &str, ldtk_type: &str, value: &str) -> FieldInstance
+    {
+        serde_json::from_str(&format!(
+            r#"{{ "__identifier": "{}", "__type": "{}", "__value": {}, "__tile": null, "defUid": 0, "realEditorValues": [] }}"#,
+            identifier, ldtk_type, value
+        )).unwrap()
+    }
+
+    fn read(fields: &[FieldInstance]) -> TestFields
+    {
+        TestFields::from_ldtk_fields(&LdtkFields { fields, owner: "test".to_string() })
+    }
+
+    #[test]
+    fn reads_every_field_it_is_given()
+    {
+        let fields = [
+            field("Locked", "Bool", "true"),
+            field("Speed", "Float", "0.5"),
+            field("Count", "Int", "3"),
+            field("Target", "Int", "12"),
+            field("Actions", "Array<String>", r#"["show_text 1", null, "set_flag a"]"#)
+        ];
+        assert_eq!(read(&fields), TestFields
+        {
+            locked: true,
+            speed: 0.5,
+            count: 3,
+            target: Some(12),
+            actions: vec!["show_text 1".to_string(), "set_flag a".to_string()]
+        });
+    }
+
+    #[test]
+    fn missing_fields_take_their_defaults_quietly()
+    {
+        assert_eq!(read(&[]), TestFields { locked: false, speed: 2., count: 7, target: None, actions: Vec::new() });
+        let fields = LdtkFields { fields: &[], owner: "test".to_string() };
+        assert_eq!(fields.read::<f32>("Speed"), Ok(None));
+    }
+
+    #[test]
+    fn blank_fields_take_their_defaults_with_a_warning()
+    {
+        let fields = [field("Speed", "Float", "null"), field("Target", "Int", "null")];
+        assert_eq!(read(&fields).speed, 2.);
+        let fields = LdtkFields { fields: &fields, owner: "test".to_string() };
+        assert_eq!(fields.read::<f32>("Speed"), Err("Field Speed of test is blank, using the default".to_string()));
+        // Optional fields may be left blank.
+        assert_eq!(fields.read::<Option<i32>>("Target"), Ok(Some(None)));
+    }
+
+    #[test]
+    fn fields_of_another_type_take_their_defaults_with_a_warning()
+    {
+        let fields = [field("Locked", "Int", "1"), field("Count", "Int", "300")];
+        let read_fields = read(&fields);
+        assert!(!read_fields.locked);
+        assert_eq!(read_fields.count, 7);
+
+        let fields = LdtkFields { fields: &fields, owner: "test".to_string() };
+        let warning = fields.read::<bool>("Locked").unwrap_err();
+        assert!(warning.starts_with("Field Locked of test should be a Bool but is Int(Some(1))"), "{}", warning);
+        let warning = fields.read::<u8>("Count").unwrap_err();
+        assert!(warning.contains("should be an Int from 0 to 255"), "{}", warning);
+    }
+}
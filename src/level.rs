@@ -0,0 +1,247 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::Level;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::headless::HeadlessLdtkPlugin;
+use crate::ldtk_fields::{ldtk_fields, FromLdtkFields};
+use crate::loading::LevelAssets;
+use crate::player::Player;
+use crate::text::InGameText;
+
+ldtk_fields! {
+    #[derive(Component, Default)]
+    pub struct PlayerSpawn
+    {
+        "AffectX" => pub affect_x: bool,
+        "AffectY" => pub affect_y: bool
+    }
+}
+
+#[derive(Bundle)]
+pub struct PlayerSpawnBundle
+{
+    pub player_spawn: PlayerSpawn,
+    pub transform: Transform
+}
+
+impl LdtkEntity for PlayerSpawnBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        _: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> PlayerSpawnBundle {
+        PlayerSpawnBundle
+        {
+            player_spawn: PlayerSpawn::from_entity(entity_instance, layer_instance),
+            transform: Transform {..default()}
+        }
+    }
+}
+
+/// Where a spawned level sits in the frame of the world entity.
+///
+/// Once [place_levels] has run this is the frame of everything in the level too, the same
+/// one players move in.
+#[derive(Component, Clone, Debug)]
+pub struct LevelBounds
+{
+    pub iid: String,
+    pub rect: Rect
+}
+
+/// The level [LevelSelection] points at, once it has spawned.
+///
+/// Changes whenever another level becomes the active one, and when the active one spawns again.
+#[derive(Resource, Default)]
+pub struct ActiveLevel
+{
+    pub entity: Option<Entity>,
+    pub iid: Option<String>,
+    pub bounds: Option<Rect>
+}
+
+/// Loads the LDtk project, spawns the world and places the player when a level comes in.
+///
+/// Every level is moved into the world entity's frame as it spawns, see [LevelBounds], and
+/// the selected one is tracked in [ActiveLevel]. Adds `LdtkPlugin` unless
+/// [HeadlessLdtkPlugin] was added first.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        if !app.is_plugin_added::<HeadlessLdtkPlugin>()
+        {
+            app.add_plugin(LdtkPlugin);
+        }
+        app.insert_resource(LdtkSettings{
+                level_background: LevelBackground::Nonexistent,
+                ..default()
+            })
+            .insert_resource(LevelSelection::Identifier("Yard".to_string()))
+            .init_resource::<ActiveLevel>()
+            .register_ldtk_entity::<PlayerSpawnBundle>("PlayerSpawn")
+            // Before anything in `Update` looks at where the level's entities are.
+            .add_system(place_levels.at_start())
+            .add_system_to_stage(CoreStage::PreUpdate, track_active_level)
+            .add_enter_system(GameState::Setup,setup)
+            .add_system(map_spawn.run_in_state(GameState::MapLoad));
+    }
+}
+
+pub fn setup(mut commands: Commands, levels: Res<LevelAssets>) {
+
+    commands.spawn(LdtkWorldBundle {
+        ldtk_handle: levels.project.clone(),
+        transform: Transform{
+            scale: Vec3::new(1.,1.,1.),
+            translation: Vec3::new(0.,-256.,0.),
+            ..default()
+        },
+
+        ..Default::default()
+    });
+
+    commands.insert_resource(NextState(GameState::MapLoad));
+}
+
+/// Whether `level` is the one `level_selection` points at. A selection by index matches any
+/// level.
+pub fn is_selected(level_selection: &LevelSelection, level: &Level) -> bool
+{
+    match level_selection
+    {
+        LevelSelection::Identifier(identifier) => level.identifier == *identifier,
+        LevelSelection::Iid(iid) => level.iid == *iid,
+        LevelSelection::Uid(uid) => level.uid == *uid,
+        LevelSelection::Index(_) => true
+    }
+}
+
+/// The level an entity spawned from LDtk belongs to.
+pub fn parent_level<'a>(parent: &Parent, level_q: &Query<&Handle<LdtkLevel>>, ldtk_levels: &'a Assets<LdtkLevel>) -> Option<&'a Level>
+{
+    level_q.get(parent.get()).ok()
+        .and_then(|handle| ldtk_levels.get(handle))
+        .map(|ldtk_level| &ldtk_level.level)
+}
+
+/// Moves the translation `bevy_ecs_ldtk` gave a newly spawned level onto its children and
+/// records it in [LevelBounds].
+///
+/// Levels only get a translation when they are laid out like in the LDtk world, see
+/// [WorldStreaming](crate::streaming::WorldStreaming). Afterwards entities of every level can be
+/// compared with players and each other without minding which level they are in.
+pub fn place_levels(
+    mut level_events: EventReader<LevelEvent>,
+    mut level_q: Query<(Entity, &mut Transform, &Handle<LdtkLevel>, Option<&Children>)>,
+    mut child_q: Query<&mut Transform, Without<Handle<LdtkLevel>>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    mut commands: Commands)
+{
+    for event in level_events.iter()
+    {
+        let iid = match event
+        {
+            LevelEvent::Spawned(iid) => iid,
+            _ => continue
+        };
+        for (l_entity, mut l_transform, l_handle, l_children) in level_q.iter_mut()
+        {
+            let level = match ldtk_levels.get(l_handle)
+            {
+                Some(ldtk_level) if ldtk_level.level.iid == *iid => &ldtk_level.level,
+                _ => continue
+            };
+            let offset = l_transform.translation.truncate();
+            for child in l_children.into_iter().flatten()
+            {
+                if let Ok(mut c_transform) = child_q.get_mut(*child)
+                {
+                    c_transform.translation += offset.extend(0.);
+                }
+            }
+            l_transform.translation = Vec3::new(0., 0., l_transform.translation.z);
+            commands.entity(l_entity).insert(LevelBounds {
+                iid: iid.to_owned(),
+                rect: Rect::from_corners(offset, offset+Vec2::new(level.px_wid as f32, level.px_hei as f32))
+            });
+        }
+    }
+}
+
+pub fn track_active_level(
+    level_q: Query<(Entity, &Handle<LdtkLevel>, &LevelBounds)>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    level_selection: Res<LevelSelection>,
+    mut active_level: ResMut<ActiveLevel>)
+{
+    let selected = level_q.iter().find(|(_, l_handle, _)| ldtk_levels.get(l_handle)
+        .is_some_and(|ldtk_level| is_selected(&level_selection, &ldtk_level.level)));
+    if let Some((l_entity, _, l_bounds)) = selected
+    {
+        if active_level.entity != Some(l_entity)
+        {
+            *active_level = ActiveLevel
+            {
+                entity: Some(l_entity),
+                iid: Some(l_bounds.iid.to_owned()),
+                bounds: Some(l_bounds.rect)
+            };
+        }
+    }
+}
+
+pub fn map_spawn(
+    mut commands: Commands,
+    ldtk_event: EventReader<LevelEvent>,
+    mut player_q: Query<&mut Transform, With<Player>>,
+    player_spawn_q: Query<(&Transform, &PlayerSpawn, &Parent), Without<Player>>,
+    level_q: Query<&Handle<LdtkLevel>>,
+    placed_q: Query<&Handle<LdtkLevel>, With<LevelBounds>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    level_selection: Res<LevelSelection>,
+    mut visible_q: Query<(&mut Visibility, &InGameText), With<InGameText>>)
+{
+    // A selected level that is already in, like a neighbour or the level itself for a door
+    // within it, does not spawn again and sends no event.
+    let selected_placed = placed_q.iter().any(|l_handle| ldtk_levels.get(l_handle)
+        .is_some_and(|ldtk_level| is_selected(&level_selection, &ldtk_level.level)));
+    if !ldtk_event.is_empty() || selected_placed
+    {
+        // Neighbouring levels may be loaded too, each with a spawn of its own.
+        let player_spawn = player_spawn_q.iter().find(|(_, _, parent)| parent_level(parent, &level_q, &ldtk_levels)
+            .is_some_and(|level| is_selected(&level_selection, level)));
+        if let Some((ps_transform, ps, _)) = player_spawn
+        {
+            for mut p_transform in player_q.iter_mut()
+            {
+                if ps.affect_x
+                {
+                    p_transform.translation.x = ps_transform.translation.x;
+                }
+                if ps.affect_y
+                {
+                    p_transform.translation.y = ps_transform.translation.y;
+                }
+            }
+        }
+
+        for (mut visible, text) in visible_q.iter_mut()
+        {
+            visible.is_visible = false;
+            if text.visible
+            {
+                visible.is_visible = true;
+            }
+        }
+
+        commands.insert_resource(NextState(GameState::Gameplay));
+    }
+}
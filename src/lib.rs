@@ -0,0 +1,130 @@
+//! Game logic for Pillars of Nature, split into Bevy plugins.
+//!
+//! [PillarsGamePlugin] registers the game state, starting with the loading screen, and every
+//! subsystem. Embedders that want to drop or replace a subsystem can add the [GameState]
+//! themselves and use [PillarsPlugins], which is a regular plugin group.
+//!
+//! None of the plugins add windowing or rendering, so the caller decides between
+//! `DefaultPlugins` and a headless setup; see [headless::HeadlessApp] for the latter.
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::app::PluginGroupBuilder;
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+pub mod abilities;
+pub mod animation;
+pub mod camera;
+pub mod collision;
+pub mod death;
+pub mod dialogue;
+pub mod doors;
+pub mod enemy;
+pub mod headless;
+pub mod health;
+pub mod input;
+pub mod interactables;
+pub mod ldtk_fields;
+pub mod level;
+pub mod loading;
+pub mod menu;
+pub mod movement;
+pub mod physics;
+pub mod player;
+pub mod projectile;
+pub mod save;
+pub mod score;
+pub mod settings;
+pub mod streaming;
+pub mod text;
+pub mod triggers;
+pub mod validate;
+
+use abilities::AbilitiesPlugin;
+use animation::AnimationPlugin;
+use camera::CameraPlugin;
+use death::DeathPlugin;
+use dialogue::DialoguePlugin;
+use doors::DoorsPlugin;
+use enemy::EnemyPlugin;
+use health::HealthPlugin;
+use input::ActionInputPlugin;
+use interactables::InteractablesPlugin;
+use level::LevelPlugin;
+use loading::LoadingPlugin;
+use menu::MenuPlugin;
+use movement::MovementPlugin;
+use physics::PhysicsPlugin;
+use player::PlayerPlugin;
+use projectile::ProjectilePlugin;
+use save::SavePlugin;
+use score::ScorePlugin;
+use settings::SettingsPlugin;
+use streaming::StreamingPlugin;
+use text::TextPlugin;
+use triggers::TriggersPlugin;
+
+pub const TILE_SIZE: f32 = 32.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState
+{
+    AssetLoading,
+    Setup,
+    MapLoad,
+    Gameplay,
+    Pause,
+    Menu
+}
+
+/// Whether a level is being played, paused or not, which is when the HUD is shown.
+pub fn in_game(state: Res<CurrentState<GameState>>) -> bool
+{
+    matches!(state.0, GameState::Gameplay | GameState::Pause)
+}
+
+/// Adds the [GameState], starting in [GameState::AssetLoading], and all of [PillarsPlugins].
+pub struct PillarsGamePlugin;
+
+impl Plugin for PillarsGamePlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_loopless_state(GameState::AssetLoading)
+            .add_plugins(PillarsPlugins);
+    }
+}
+
+/// Every gameplay subsystem. Expects the [GameState] to already be registered.
+pub struct PillarsPlugins;
+
+impl PluginGroup for PillarsPlugins
+{
+    fn build(self) -> PluginGroupBuilder
+    {
+        PluginGroupBuilder::start::<Self>()
+            .add(LoadingPlugin)
+            .add(ActionInputPlugin)
+            .add(LevelPlugin)
+            .add(StreamingPlugin)
+            .add(MovementPlugin)
+            .add(PhysicsPlugin)
+            .add(PlayerPlugin)
+            .add(DeathPlugin)
+            .add(HealthPlugin)
+            .add(CameraPlugin)
+            .add(InteractablesPlugin)
+            .add(DoorsPlugin)
+            .add(ScorePlugin)
+            .add(EnemyPlugin)
+            .add(TextPlugin)
+            .add(TriggersPlugin)
+            .add(DialoguePlugin)
+            .add(ProjectilePlugin)
+            .add(AbilitiesPlugin)
+            .add(AnimationPlugin)
+            .add(SavePlugin)
+            .add(SettingsPlugin)
+            .add(MenuPlugin)
+    }
+}
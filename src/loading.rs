@@ -0,0 +1,310 @@
+use bevy::asset::{Asset, LoadState};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::animation::PLAYER_ANIMATIONS_PATH;
+use crate::camera::CAMERA_CONFIG_PATH;
+use crate::dialogue::DIALOGUE_PATH;
+use crate::input::INPUT_BINDINGS_PATH;
+use crate::movement::MOVEMENT_CONFIG_PATH;
+use crate::triggers::TriggerAction;
+
+pub const LDTK_PROJECT_PATH: &str = "test_32.ldtk";
+pub const FONT_PATH: &str = "Lato-Black.ttf";
+pub const PLAYER_TEXTURE: &str = "protagonist_silhouette.png";
+pub const NPC_TEXTURE: &str = "protagonist_alpha.png";
+pub const SLIME_TEXTURE: &str = "slime.png";
+/// Tileset coins are cut from.
+pub const TILEMAP_TEXTURE: &str = "monochrome_tilemap_transparent_packed.png";
+pub const FIRE_TOTEM_TEXTURE: &str = "fire_totem_32.png";
+pub const EARTH_TOTEM_TEXTURE: &str = "earth_totem.png";
+pub const WATER_TOTEM_TEXTURE: &str = "water_totem.png";
+pub const AIR_TOTEM_TEXTURE: &str = "air_totem.png";
+
+#[derive(Resource)]
+pub struct LevelAssets
+{
+    pub project: Handle<LdtkAsset>
+}
+
+#[derive(Resource)]
+pub struct FontAssets
+{
+    pub main: Handle<Font>
+}
+
+#[derive(Resource)]
+pub struct TextureAssets
+{
+    pub player: Handle<Image>,
+    pub npc: Handle<Image>,
+    pub slime: Handle<Image>,
+    pub tilemap: Handle<Image>,
+    pub fire_totem: Handle<Image>,
+    pub earth_totem: Handle<Image>,
+    pub water_totem: Handle<Image>,
+    pub air_totem: Handle<Image>
+}
+
+/// Sounds played by LDtk triggers, by their asset path.
+#[derive(Resource, Default)]
+pub struct AudioAssets
+{
+    pub sounds: HashMap<String, Handle<AudioSource>>
+}
+
+/// Assets still being waited for in [GameState::AssetLoading], and the ones that failed.
+#[derive(Resource, Default)]
+pub struct LoadingAssets
+{
+    /// Asset path and handle of everything not loaded yet.
+    pub pending: Vec<(String, HandleUntyped)>,
+    pub loaded: usize,
+    pub failed: Vec<String>,
+    /// Whether the sounds used by triggers have been looked up in the LDtk project.
+    sounds_found: bool
+}
+
+impl LoadingAssets
+{
+    fn track<T: Asset>(&mut self, path: &str, handle: &Handle<T>)
+    {
+        self.pending.push((path.to_string(), handle.clone_untyped()));
+    }
+
+    /// Share of the assets found so far that are done, failed ones included.
+    pub fn progress(&self) -> f32
+    {
+        let done = self.loaded+self.failed.len();
+        let total = done+self.pending.len();
+        if total == 0 { 1. } else { done as f32/total as f32 }
+    }
+}
+
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingBar;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+/// Starts loading every asset the game needs and keeps them in typed handle resources.
+///
+/// The game starts in [GameState::AssetLoading] and shows a progress bar until everything,
+/// including the sounds named by triggers in the LDtk project, has loaded. Assets that fail
+/// are listed on screen and in the log, and the game does not go on to the title menu.
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<LoadingAssets>()
+            .init_resource::<AudioAssets>()
+            .add_startup_system(load_assets)
+            .add_enter_system(GameState::AssetLoading, spawn_loading_screen)
+            .add_exit_system(GameState::AssetLoading, despawn_loading_screen)
+            .add_system(find_sounds)
+            .add_system(check_loading.run_in_state(GameState::AssetLoading))
+            .add_system(draw_loading_screen.run_in_state(GameState::AssetLoading));
+    }
+}
+
+pub fn load_assets(asset_server: Res<AssetServer>, mut loading: ResMut<LoadingAssets>, mut commands: Commands)
+{
+    let levels = LevelAssets { project: asset_server.load(LDTK_PROJECT_PATH) };
+    let fonts = FontAssets { main: asset_server.load(FONT_PATH) };
+    let textures = TextureAssets {
+        player: asset_server.load(PLAYER_TEXTURE),
+        npc: asset_server.load(NPC_TEXTURE),
+        slime: asset_server.load(SLIME_TEXTURE),
+        tilemap: asset_server.load(TILEMAP_TEXTURE),
+        fire_totem: asset_server.load(FIRE_TOTEM_TEXTURE),
+        earth_totem: asset_server.load(EARTH_TOTEM_TEXTURE),
+        water_totem: asset_server.load(WATER_TOTEM_TEXTURE),
+        air_totem: asset_server.load(AIR_TOTEM_TEXTURE)
+    };
+
+    loading.track(LDTK_PROJECT_PATH, &levels.project);
+    loading.track(FONT_PATH, &fonts.main);
+    for (path, handle) in [
+        (PLAYER_TEXTURE, &textures.player),
+        (NPC_TEXTURE, &textures.npc),
+        (SLIME_TEXTURE, &textures.slime),
+        (TILEMAP_TEXTURE, &textures.tilemap),
+        (FIRE_TOTEM_TEXTURE, &textures.fire_totem),
+        (EARTH_TOTEM_TEXTURE, &textures.earth_totem),
+        (WATER_TOTEM_TEXTURE, &textures.water_totem),
+        (AIR_TOTEM_TEXTURE, &textures.air_totem)
+    ]
+    {
+        loading.track(path, handle);
+    }
+    // Loaded again by their own plugins, which get the same handles back.
+    for path in [INPUT_BINDINGS_PATH, MOVEMENT_CONFIG_PATH, CAMERA_CONFIG_PATH, DIALOGUE_PATH, PLAYER_ANIMATIONS_PATH]
+    {
+        let handle = asset_server.load_untyped(path);
+        loading.pending.push((path.to_string(), handle));
+    }
+
+    commands.insert_resource(levels);
+    commands.insert_resource(fonts);
+    commands.insert_resource(textures);
+}
+
+/// Loads every `play_sound` of the triggers once the LDtk project is in.
+pub fn find_sounds(
+    levels: Option<Res<LevelAssets>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    asset_server: Res<AssetServer>,
+    mut audio: ResMut<AudioAssets>,
+    mut loading: ResMut<LoadingAssets>)
+{
+    if loading.sounds_found
+    {
+        return;
+    }
+    let project = match levels.as_ref().and_then(|levels| ldtk_assets.get(&levels.project))
+    {
+        Some(ldtk_asset) => &ldtk_asset.project,
+        None => return
+    };
+    loading.sounds_found = true;
+
+    let actions = project.levels.iter()
+        .flat_map(|level| level.layer_instances.iter().flatten())
+        .flat_map(|layer| layer.entity_instances.iter())
+        .filter(|entity| entity.identifier == *"Trigger")
+        .flat_map(|entity| entity.field_instances.iter())
+        .filter(|f| f.identifier == *"Actions");
+    for field_instance in actions
+    {
+        if let FieldValue::Strings(action_fields) = &field_instance.value
+        {
+            for action in action_fields.iter().flatten()
+            {
+                if let Some(TriggerAction::PlaySound(path)) = TriggerAction::parse(action)
+                {
+                    if !audio.sounds.contains_key(&path)
+                    {
+                        let handle = asset_server.load(path.as_str());
+                        loading.track(&path, &handle);
+                        audio.sounds.insert(path, handle);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn check_loading(asset_server: Res<AssetServer>, mut loading: ResMut<LoadingAssets>, mut commands: Commands)
+{
+    let loading = &mut *loading;
+    let mut i = 0;
+    while i < loading.pending.len()
+    {
+        let (path, handle) = &loading.pending[i];
+        match asset_server.get_load_state(handle)
+        {
+            LoadState::Loaded =>
+            {
+                loading.loaded += 1;
+                loading.pending.swap_remove(i);
+            },
+            LoadState::Failed =>
+            {
+                error!("Could not load {}: make sure it exists in the assets folder and is valid", path);
+                loading.failed.push(path.to_owned());
+                loading.pending.swap_remove(i);
+            },
+            _ => i += 1
+        }
+    }
+
+    if loading.pending.is_empty() && loading.sounds_found && loading.failed.is_empty()
+    {
+        commands.insert_resource(NextState(GameState::Menu));
+    }
+}
+
+pub fn spawn_loading_screen(asset_server: Res<AssetServer>, mut commands: Commands)
+{
+    let font = asset_server.load(FONT_PATH);
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: Color::rgb_u8(16, 0, 16).into(),
+        ..default()
+    }, LoadingScreen)).with_children(|screen| {
+        screen.spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(60.), Val::Px(16.)),
+                border: UiRect::all(Val::Px(2.)),
+                ..default()
+            },
+            background_color: Color::rgb(0.8, 0.8, 0.8).into(),
+            ..default()
+        }).with_children(|frame| {
+            frame.spawn((NodeBundle {
+                style: Style { size: Size::new(Val::Percent(0.), Val::Percent(100.)), ..default() },
+                background_color: Color::rgb(1., 0.67, 0.16).into(),
+                ..default()
+            }, LoadingBar));
+        });
+        screen.spawn((TextBundle::from_section("Loading", TextStyle {
+            font,
+            font_size: 18.0,
+            color: Color::rgb(0.8, 0.8, 0.8)
+        }).with_style(Style { margin: UiRect::top(Val::Px(12.)), ..default() }), LoadingText));
+    });
+}
+
+pub fn draw_loading_screen(
+    loading: Res<LoadingAssets>,
+    mut bar_q: Query<&mut Style, With<LoadingBar>>,
+    mut text_q: Query<(&mut Text, &mut Style), (With<LoadingText>, Without<LoadingBar>)>)
+{
+    if !loading.is_changed()
+    {
+        return;
+    }
+    for mut style in bar_q.iter_mut()
+    {
+        style.size.width = Val::Percent(loading.progress()*100.);
+    }
+    for (mut text, mut style) in text_q.iter_mut()
+    {
+        text.sections[0].value = if loading.failed.is_empty()
+        {
+            format!("Loading {}/{}", loading.loaded, loading.loaded+loading.pending.len())
+        }
+        else
+        {
+            format!("Could not load:\n{}", loading.failed.join("\n"))
+        };
+        if !loading.failed.is_empty()
+        {
+            style.max_size.width = Val::Percent(90.);
+            text.sections[0].style.color = Color::rgb_u8(220, 40, 60);
+        }
+    }
+}
+
+pub fn despawn_loading_screen(screen_q: Query<Entity, With<LoadingScreen>>, mut commands: Commands)
+{
+    for screen in screen_q.iter()
+    {
+        commands.entity(screen).despawn_recursive();
+    }
+}
@@ -0,0 +1,418 @@
+use bevy::app::AppExit;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::loading::FontAssets;
+use crate::input::{Action, InputBindings, PlayerInputs, STICK_DEADZONE};
+use crate::save::{LoadGame, SaveGame, SaveLabel, SaveSlots, SAVE_SLOTS};
+use crate::settings::{reset_bindings, Settings, WINDOW_SCALES};
+
+const MENU_TEXT: Color = Color::rgb(0.8, 0.8, 0.8);
+const MENU_SELECTED: Color = Color::rgb(1., 0.67, 0.16);
+const MENU_DISABLED: Color = Color::rgb(0.35, 0.35, 0.35);
+
+/// Menu presses of this frame from the keyboard and every gamepad, whatever the bindings.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct MenuInput
+{
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuScreen
+{
+    Title,
+    Pause,
+    Settings
+}
+
+/// One line of a menu screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem
+{
+    NewGame,
+    Continue,
+    SaveSlot,
+    Settings,
+    Quit,
+    Resume,
+    SaveAndQuit,
+    Volume,
+    WindowScale,
+    /// Which player's bindings are listed below it.
+    BindingsPlayer,
+    Binding(Action),
+    ResetBindings,
+    Back
+}
+
+impl MenuScreen
+{
+    pub fn items(&self) -> Vec<MenuItem>
+    {
+        match self
+        {
+            MenuScreen::Title => vec![MenuItem::NewGame, MenuItem::Continue, MenuItem::SaveSlot, MenuItem::Settings, MenuItem::Quit],
+            MenuScreen::Pause => vec![MenuItem::Resume, MenuItem::Settings, MenuItem::SaveAndQuit],
+            MenuScreen::Settings =>
+            {
+                let mut items = vec![MenuItem::Volume, MenuItem::WindowScale, MenuItem::BindingsPlayer];
+                items.extend(Action::ALL.map(MenuItem::Binding));
+                items.extend([MenuItem::ResetBindings, MenuItem::Back]);
+                items
+            }
+        }
+    }
+}
+
+/// The screen shown while in [GameState::Menu] or [GameState::Pause].
+#[derive(Resource, Debug, Clone)]
+pub struct ActiveMenu
+{
+    pub screen: MenuScreen,
+    /// Index into the screen's items.
+    pub selected: usize,
+    /// Screen that going back from the settings returns to.
+    pub previous: MenuScreen,
+    /// Player slot whose bindings the settings screen shows.
+    pub bindings_player: usize,
+    /// Action waiting for a key or button to be bound to it.
+    pub rebinding: Option<Action>
+}
+
+impl Default for ActiveMenu
+{
+    fn default() -> Self
+    {
+        ActiveMenu { screen: MenuScreen::Title, selected: 0, previous: MenuScreen::Title, bindings_player: 0, rebinding: None }
+    }
+}
+
+impl ActiveMenu
+{
+    fn open(&mut self, screen: MenuScreen)
+    {
+        self.previous = self.screen;
+        self.screen = screen;
+        self.selected = 0;
+        self.rebinding = None;
+    }
+}
+
+#[derive(Component)]
+pub struct MenuRoot;
+
+/// The title menu, the pause overlay and the settings screen.
+///
+/// The title menu follows the loading screen. During play the [Action::Pause] binding switches to
+/// [GameState::Pause], which holds every system that only runs in [GameState::Gameplay].
+/// Menus are driven by [MenuInput], so arrows, WASD and any gamepad work in them.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<MenuInput>()
+            .init_resource::<ActiveMenu>()
+            .add_system_to_stage(CoreStage::PreUpdate, read_menu_input.after(InputSystem))
+            .add_enter_system(GameState::Menu, open_title)
+            .add_enter_system(GameState::Pause, open_pause)
+            .add_system(pause_game.run_in_state(GameState::Gameplay))
+            .add_system(navigate_menu.run_if(in_menu).before(SaveLabel::Write))
+            .add_system_to_stage(CoreStage::PostUpdate, draw_menu);
+    }
+}
+
+pub fn in_menu(state: Res<CurrentState<GameState>>) -> bool
+{
+    matches!(state.0, GameState::Menu | GameState::Pause)
+}
+
+pub fn read_menu_input(
+    kb: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    mut last_stick: Local<Vec2>,
+    mut input: ResMut<MenuInput>)
+{
+    let button = |types: &[GamepadButtonType]| gamepads.iter()
+        .any(|gamepad| buttons.any_just_pressed(types.iter().map(|t| GamepadButton::new(gamepad, *t))));
+
+    // The stick counts once each time it is pushed past the deadzone.
+    let mut stick = Vec2::ZERO;
+    for gamepad in gamepads.iter()
+    {
+        let axis = |axis| axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.);
+        stick += Vec2::new(axis(GamepadAxisType::LeftStickX), axis(GamepadAxisType::LeftStickY));
+    }
+    let stick = Vec2::new(
+        if stick.x.abs() >= STICK_DEADZONE { stick.x.signum() } else { 0. },
+        if stick.y.abs() >= STICK_DEADZONE { stick.y.signum() } else { 0. }
+    );
+    let pushed = |now: f32, before: f32, dir: f32| now == dir && before != dir;
+
+    *input = MenuInput
+    {
+        up: kb.any_just_pressed([KeyCode::Up, KeyCode::W]) || button(&[GamepadButtonType::DPadUp]) || pushed(stick.y, last_stick.y, 1.),
+        down: kb.any_just_pressed([KeyCode::Down, KeyCode::S]) || button(&[GamepadButtonType::DPadDown]) || pushed(stick.y, last_stick.y, -1.),
+        left: kb.any_just_pressed([KeyCode::Left, KeyCode::A]) || button(&[GamepadButtonType::DPadLeft]) || pushed(stick.x, last_stick.x, -1.),
+        right: kb.any_just_pressed([KeyCode::Right, KeyCode::D]) || button(&[GamepadButtonType::DPadRight]) || pushed(stick.x, last_stick.x, 1.),
+        confirm: kb.any_just_pressed([KeyCode::Return, KeyCode::Space]) || button(&[GamepadButtonType::South]),
+        back: kb.any_just_pressed([KeyCode::Escape, KeyCode::Back]) || button(&[GamepadButtonType::East, GamepadButtonType::Start])
+    };
+    *last_stick = stick;
+}
+
+pub fn open_title(mut menu: ResMut<ActiveMenu>)
+{
+    *menu = ActiveMenu::default();
+}
+
+pub fn open_pause(mut menu: ResMut<ActiveMenu>)
+{
+    *menu = ActiveMenu { screen: MenuScreen::Pause, previous: MenuScreen::Pause, ..default() };
+}
+
+pub fn pause_game(inputs: Res<PlayerInputs>, mut commands: Commands)
+{
+    if inputs.any_just_pressed(Action::Pause)
+    {
+        commands.insert_resource(NextState(GameState::Pause));
+    }
+}
+
+/// Adds `delta`, rounded to a tenth so repeated steps don't drift.
+fn step(value: f32, delta: f32, min: f32, max: f32) -> f32
+{
+    (((value+delta)*10.).round()/10.).clamp(min, max)
+}
+
+fn cycle(value: usize, delta: i32, count: usize) -> usize
+{
+    (value as i32+delta).rem_euclid(count.max(1) as i32) as usize
+}
+
+pub fn navigate_menu(
+    input: Res<MenuInput>,
+    kb: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut menu: ResMut<ActiveMenu>,
+    mut settings: ResMut<Settings>,
+    mut bindings: ResMut<InputBindings>,
+    mut slots: ResMut<SaveSlots>,
+    binding_assets: Res<Assets<InputBindings>>,
+    asset_server: Res<AssetServer>,
+    mut load: EventWriter<LoadGame>,
+    mut save: EventWriter<SaveGame>,
+    mut exit: EventWriter<AppExit>,
+    mut commands: Commands)
+{
+    if let Some(action) = menu.rebinding
+    {
+        let player = menu.bindings_player;
+        let key = kb.get_just_pressed().next().copied();
+        let button = buttons.get_just_pressed().next().map(|button| button.button_type);
+        if let Some(player_bindings) = bindings.players.get_mut(player)
+        {
+            if let Some(key) = key
+            {
+                player_bindings.keys.insert(action, vec![key]);
+            }
+            if let Some(button) = button
+            {
+                player_bindings.buttons.insert(action, vec![button]);
+            }
+        }
+        if key.is_some() || button.is_some()
+        {
+            settings.bindings = Some(bindings.clone());
+            menu.rebinding = None;
+        }
+        return;
+    }
+
+    let items = menu.screen.items();
+    if input.up
+    {
+        menu.selected = cycle(menu.selected, -1, items.len());
+    }
+    if input.down
+    {
+        menu.selected = cycle(menu.selected, 1, items.len());
+    }
+    let item = items[menu.selected.min(items.len()-1)];
+
+    let delta = if input.left { -1 } else if input.right || input.confirm { 1 } else { 0 };
+    if delta != 0
+    {
+        match item
+        {
+            MenuItem::SaveSlot => slots.active = cycle(slots.active as usize, delta, SAVE_SLOTS as usize) as u8,
+            MenuItem::Volume =>
+            {
+                // Confirm wraps around so the value can be set with one button.
+                settings.volume = if input.confirm && settings.volume >= 1. { 0. } else { step(settings.volume, 0.1*delta as f32, 0., 1.) };
+            },
+            MenuItem::WindowScale =>
+            {
+                let (min, max) = WINDOW_SCALES;
+                settings.window_scale = if input.confirm && settings.window_scale >= max { min } else { step(settings.window_scale, 0.5*delta as f32, min, max) };
+            },
+            MenuItem::BindingsPlayer => menu.bindings_player = cycle(menu.bindings_player, delta, bindings.players.len()),
+            _ => ()
+        }
+    }
+
+    if input.confirm
+    {
+        match item
+        {
+            MenuItem::NewGame => commands.insert_resource(NextState(GameState::Setup)),
+            MenuItem::Continue if slots.exists(slots.active) =>
+            {
+                load.send(LoadGame { slot: slots.active });
+                commands.insert_resource(NextState(GameState::Setup));
+            },
+            MenuItem::Settings => menu.open(MenuScreen::Settings),
+            MenuItem::Quit => exit.send(AppExit),
+            MenuItem::Resume => commands.insert_resource(NextState(GameState::Gameplay)),
+            MenuItem::SaveAndQuit =>
+            {
+                save.send(SaveGame { slot: slots.active });
+                exit.send(AppExit);
+            },
+            MenuItem::Binding(action) => menu.rebinding = Some(action),
+            MenuItem::ResetBindings => reset_bindings(&mut settings, &mut bindings, &binding_assets, &asset_server, &mut commands),
+            MenuItem::Back => go_back(&mut menu, &mut commands),
+            _ => ()
+        }
+    }
+    else if input.back
+    {
+        go_back(&mut menu, &mut commands);
+    }
+}
+
+fn go_back(menu: &mut ActiveMenu, commands: &mut Commands)
+{
+    match menu.screen
+    {
+        MenuScreen::Settings =>
+        {
+            let previous = menu.previous;
+            menu.open(previous);
+        },
+        MenuScreen::Pause => commands.insert_resource(NextState(GameState::Gameplay)),
+        MenuScreen::Title => ()
+    }
+}
+
+fn item_label(item: MenuItem, menu: &ActiveMenu, settings: &Settings, bindings: &InputBindings, slots: &SaveSlots) -> String
+{
+    match item
+    {
+        MenuItem::NewGame => "New Game".to_string(),
+        MenuItem::Continue => "Continue".to_string(),
+        MenuItem::SaveSlot => format!("Save Slot: < {} >", slots.active+1),
+        MenuItem::Settings => "Settings".to_string(),
+        MenuItem::Quit => "Quit".to_string(),
+        MenuItem::Resume => "Resume".to_string(),
+        MenuItem::SaveAndQuit => "Save and Quit".to_string(),
+        MenuItem::Volume => format!("Volume: < {:.0}% >", settings.volume*100.),
+        MenuItem::WindowScale => format!("Window Scale: < {}x >", settings.window_scale),
+        MenuItem::BindingsPlayer => format!("Bindings for Player < {} >", menu.bindings_player+1),
+        MenuItem::Binding(action) if menu.rebinding == Some(action) => format!("{:?}: press a key or button", action),
+        MenuItem::Binding(action) =>
+        {
+            let player = bindings.players.get(menu.bindings_player);
+            let key = player.and_then(|player| player.keys.get(&action)).and_then(|keys| keys.first());
+            let button = player.and_then(|player| player.buttons.get(&action)).and_then(|buttons| buttons.first());
+            format!(
+                "{:?}: {} / {}",
+                action,
+                key.map_or("-".to_string(), |key| format!("{:?}", key)),
+                button.map_or("-".to_string(), |button| format!("{:?}", button))
+            )
+        },
+        MenuItem::ResetBindings => "Reset Bindings".to_string(),
+        MenuItem::Back => "Back".to_string()
+    }
+}
+
+/// Rebuilds the menu whenever what it shows changes, and removes it outside the menus.
+pub fn draw_menu(
+    state: Res<CurrentState<GameState>>,
+    menu: Res<ActiveMenu>,
+    settings: Res<Settings>,
+    bindings: Res<InputBindings>,
+    slots: Res<SaveSlots>,
+    root_q: Query<Entity, With<MenuRoot>>,
+    fonts: Res<FontAssets>,
+    mut commands: Commands)
+{
+    let shown = matches!(state.0, GameState::Menu | GameState::Pause);
+    let changed = state.is_changed() || menu.is_changed() || settings.is_changed() || bindings.is_changed() || slots.is_changed();
+    let in_sync = root_q.is_empty() != shown;
+    if in_sync && !changed
+    {
+        return;
+    }
+    for root in root_q.iter()
+    {
+        commands.entity(root).despawn_recursive();
+    }
+    if !shown
+    {
+        return;
+    }
+
+    let font = fonts.main.clone();
+    let heading = match menu.screen
+    {
+        MenuScreen::Title => "PILLARS OF NATURE",
+        MenuScreen::Pause => "Paused",
+        MenuScreen::Settings => "Settings"
+    };
+    // The title covers the screen; over the game, the level stays visible behind.
+    let background = if state.0 == GameState::Menu { Color::rgb_u8(16, 0, 16) } else { Color::rgba(0., 0., 0., 0.7) };
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: background.into(),
+        z_index: ZIndex::Global(10),
+        ..default()
+    }, MenuRoot)).with_children(|root| {
+        root.spawn(TextBundle::from_section(heading, TextStyle {
+            font: font.clone(),
+            font_size: 36.0,
+            color: Color::rgb_u8(255, 200, 80)
+        }).with_style(Style { margin: UiRect::bottom(Val::Px(24.)), ..default() }));
+
+        let small = menu.screen == MenuScreen::Settings;
+        for (index, item) in menu.screen.items().into_iter().enumerate()
+        {
+            let disabled = item == MenuItem::Continue && !slots.exists(slots.active);
+            let color = if index == menu.selected { MENU_SELECTED } else if disabled { MENU_DISABLED } else { MENU_TEXT };
+            root.spawn(TextBundle::from_section(item_label(item, &menu, &settings, &bindings, &slots), TextStyle {
+                font: font.clone(),
+                font_size: if small { 18.0 } else { 24.0 },
+                color
+            }).with_style(Style { margin: UiRect::vertical(Val::Px(if small { 2. } else { 6. })), ..default() }));
+        }
+    });
+}
@@ -0,0 +1,157 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use crate::ldtk_fields::ldtk_fields;
+
+/// Asset the global [MovementConfig] is read from. Edits are picked up while the game runs.
+pub const MOVEMENT_CONFIG_PATH: &str = "default.movement.ron";
+
+/// How actors move, in pixels and seconds.
+///
+/// Loaded from [MOVEMENT_CONFIG_PATH] and kept as a resource; a [MovementOverride] on an
+/// actor replaces single values for just that entity.
+#[derive(Resource, Deserialize, TypeUuid, Debug, Clone, Copy, PartialEq)]
+#[uuid = "4f5a0c83-2d6e-4b8a-9d3f-7c1e6a2b9e54"]
+#[serde(default)]
+pub struct MovementConfig
+{
+    pub run_speed: f32,
+    pub jump_impulse: f32,
+    /// Upward speed a jump is cut down to when the jump button is let go early.
+    pub jump_cut: f32,
+    pub gravity: f32,
+    /// Fastest an actor can fall. Negative, since it is a downward velocity.
+    pub terminal_velocity: f32,
+    /// Seconds after walking off a ledge in which a jump still counts as grounded.
+    pub coyote_time: f32
+}
+
+impl Default for MovementConfig
+{
+    fn default() -> Self
+    {
+        MovementConfig
+        {
+            run_speed: 240.,
+            jump_impulse: 840.,
+            jump_cut: 180.,
+            gravity: 3600.,
+            terminal_velocity: -720.,
+            coyote_time: 5./60.
+        }
+    }
+}
+
+impl MovementConfig
+{
+    /// This config with every value set in `overrides` replaced.
+    pub fn with(&self, overrides: Option<&MovementOverride>) -> MovementConfig
+    {
+        match overrides
+        {
+            Some(overrides) => MovementConfig
+            {
+                run_speed: overrides.run_speed.unwrap_or(self.run_speed),
+                jump_impulse: overrides.jump_impulse.unwrap_or(self.jump_impulse),
+                jump_cut: overrides.jump_cut.unwrap_or(self.jump_cut),
+                gravity: overrides.gravity.unwrap_or(self.gravity),
+                terminal_velocity: overrides.terminal_velocity.unwrap_or(self.terminal_velocity),
+                coyote_time: overrides.coyote_time.unwrap_or(self.coyote_time)
+            },
+            None => *self
+        }
+    }
+}
+
+ldtk_fields! {
+    /// Per-entity replacements for values of the global [MovementConfig].
+    ///
+    /// Read from the optional float fields of an LDtk entity, named after each value.
+    #[derive(Component, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+    #[serde(default)]
+    pub struct MovementOverride
+    {
+        "RunSpeed" => pub run_speed: Option<f32>,
+        "JumpImpulse" => pub jump_impulse: Option<f32>,
+        "JumpCut" => pub jump_cut: Option<f32>,
+        "Gravity" => pub gravity: Option<f32>,
+        "TerminalVelocity" => pub terminal_velocity: Option<f32>,
+        "CoyoteTime" => pub coyote_time: Option<f32>
+    }
+}
+
+#[derive(Default)]
+pub struct MovementConfigLoader;
+
+impl AssetLoader for MovementConfigLoader
+{
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let config: MovementConfig = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["movement.ron"]
+    }
+}
+
+/// Handle keeping the loaded [MovementConfig] asset alive.
+#[derive(Resource)]
+pub struct MovementConfigHandle(pub Handle<MovementConfig>);
+
+/// Loads the [MovementConfig] and copies it into the resource whenever the file changes.
+///
+/// The built-in defaults stay in place until the file has loaded, or if it fails to parse.
+pub struct MovementPlugin;
+
+impl Plugin for MovementPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_asset::<MovementConfig>()
+            .init_asset_loader::<MovementConfigLoader>()
+            .init_resource::<MovementConfig>()
+            .add_startup_system(load_movement_config)
+            .add_system(apply_movement_config);
+    }
+}
+
+pub fn load_movement_config(asset_server: Res<AssetServer>, mut commands: Commands)
+{
+    commands.insert_resource(MovementConfigHandle(asset_server.load(MOVEMENT_CONFIG_PATH)));
+}
+
+pub fn apply_movement_config(
+    mut events: EventReader<AssetEvent<MovementConfig>>,
+    handle: Option<Res<MovementConfigHandle>>,
+    configs: Res<Assets<MovementConfig>>,
+    mut config: ResMut<MovementConfig>)
+{
+    if let Some(handle) = handle
+    {
+        for event in events.iter()
+        {
+            match event
+            {
+                AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed } if *changed == handle.0 =>
+                {
+                    if let Some(loaded) = configs.get(changed)
+                    {
+                        *config = *loaded;
+                    }
+                },
+                _ => ()
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::collision::{build_collision_grid, collide_tiles, CollisionGrid};
+use crate::death::{Dead, DeathCause, PlayerDied};
+use crate::input::PlayerId;
+use crate::level::{ActiveLevel, LevelBounds};
+use crate::movement::{MovementConfig, MovementOverride};
+
+/// Name of the fixed timestep physics runs in.
+pub const PHYSICS_TIMESTEP: &str = "physics";
+/// Length of one physics tick, in seconds.
+pub const PHYSICS_DT: f32 = 1./60.;
+
+#[derive(Component, Default)]
+pub struct Actor{
+    pub grounded: bool,
+    pub jumped: bool,
+    /// Seconds left in which the actor may still jump after walking off a ledge.
+    pub coyote_time: f32,
+    pub jump_count: u8,
+    pub jump_limit: u8
+}
+
+/// Velocity in pixels per second.
+#[derive(Component, Default)]
+pub struct Vel(pub Vec2);
+
+/// Translations of an [Actor] before and after the last physics tick.
+///
+/// While [PhysicsInterpolation] is on, the `Transform` is blended between the two for
+/// rendering and put back to `current` at the start of the next frame, so gameplay systems
+/// only ever see physics positions.
+#[derive(Component, Default)]
+pub struct Interpolated
+{
+    pub previous: Vec3,
+    pub current: Vec3
+}
+
+/// Whether actors are drawn between physics ticks rather than on them.
+#[derive(Resource)]
+pub struct PhysicsInterpolation(pub bool);
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, SystemLabel)]
+pub enum PhysicsLabel
+{
+    /// Actor transforms hold their interpolated positions after this.
+    Interpolate
+}
+
+/// Gravity and tile collision for every [Actor], at [PHYSICS_DT] no matter the frame rate.
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<CollisionGrid>()
+            .insert_resource(PhysicsInterpolation(true))
+            .add_fixed_timestep(Duration::from_secs_f32(PHYSICS_DT), PHYSICS_TIMESTEP)
+            // Levels get their bounds in `Update`.
+            .add_system_to_stage(CoreStage::PostUpdate, build_collision_grid)
+            .add_system(add_interpolation)
+            .add_fixed_timestep_system(PHYSICS_TIMESTEP, 0, actor_physics.run_in_state(GameState::Gameplay))
+            .add_system_to_stage(CoreStage::First, restore_physics_translation)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                interpolate_actors
+                    .label(PhysicsLabel::Interpolate)
+                    .before(TransformSystem::TransformPropagate)
+            );
+    }
+}
+
+pub fn add_interpolation(actor_q: Query<(Entity, &Transform), Added<Actor>>, mut commands: Commands)
+{
+    for (a_entity, a_transform) in actor_q.iter()
+    {
+        commands.entity(a_entity).insert(Interpolated {
+            previous: a_transform.translation,
+            current: a_transform.translation
+        });
+    }
+}
+
+pub fn restore_physics_translation(mut actor_q: Query<(&mut Transform, &Interpolated)>)
+{
+    for (mut a_transform, interpolated) in actor_q.iter_mut()
+    {
+        a_transform.translation = interpolated.current;
+    }
+}
+
+pub fn interpolate_actors(
+    interpolation: Res<PhysicsInterpolation>,
+    timesteps: Res<FixedTimesteps>,
+    mut actor_q: Query<(&mut Transform, &mut Interpolated)>)
+{
+    let alpha = timesteps.get(PHYSICS_TIMESTEP).map_or(1., |info| info.overstep() as f32).min(1.);
+    for (mut a_transform, mut interpolated) in actor_q.iter_mut()
+    {
+        if a_transform.translation != interpolated.current
+        {
+            // Moved outside of physics, e.g. placed at a spawn point: snap there.
+            interpolated.previous = a_transform.translation;
+            interpolated.current = a_transform.translation;
+        }
+        else if interpolation.0
+        {
+            a_transform.translation = interpolated.previous.lerp(interpolated.current, alpha);
+        }
+    }
+}
+
+pub fn actor_physics(
+    mut actor_q: Query<(&mut Vel, &mut Transform, &mut Actor, &Handle<Image>, Entity, &Sprite, Option<&mut Interpolated>, Option<&MovementOverride>, Option<&PlayerId>), (With<Actor>, Without<Dead>)>,
+    timesteps: Res<FixedTimesteps>,
+    movement: Res<MovementConfig>,
+    grid: Res<CollisionGrid>,
+    level_q: Query<&LevelBounds>,
+    active_level: Res<ActiveLevel>,
+    images: Res<Assets<Image>>,
+    mut died: EventWriter<PlayerDied>,
+    mut commands: Commands)
+{
+    let dt = timesteps.current().timestep().as_secs_f32();
+    for (mut a_vel, mut a_transform, mut actor, a_image, a_entity, a_sprite, mut interpolated, a_overrides, a_player) in actor_q.iter_mut()
+    {
+        let movement = movement.with(a_overrides);
+        if let Some(interpolated) = interpolated.as_mut()
+        {
+            interpolated.previous = a_transform.translation;
+        }
+
+        if actor.grounded
+        {
+            actor.coyote_time = movement.coyote_time;
+            actor.jump_count = actor.jump_limit;
+        }
+        else
+        {
+        if actor.coyote_time > 0.
+        {
+            actor.jump_count = actor.jump_limit;
+        }
+        else if actor.jump_count == actor.jump_limit && actor.jump_count != 0
+        {
+            actor.jump_count = actor.jump_limit-1;
+        }
+        }
+
+        actor.grounded = false;
+        actor.coyote_time = (actor.coyote_time-dt).max(0.);
+
+        let mut a_size = a_sprite.custom_size.unwrap();
+        if a_sprite.custom_size.is_none()
+        {
+            a_size = images.get(a_image).unwrap().size();
+        }
+
+
+        if a_vel.0.y > movement.terminal_velocity
+        {
+            a_vel.0.y -= movement.gravity*dt;
+        }
+
+        // How far the actor moves this tick; collisions zero out the blocked axes.
+        let mut motion = a_vel.0*dt;
+        if collide_tiles(&mut a_transform.translation, &mut motion, a_size, |center, size| grid.solids_in(center, size))
+        {
+            actor.grounded = true;
+            actor.jumped = false;
+        }
+        if motion.x == 0.
+        {
+            a_vel.0.x = 0.;
+        }
+        if motion.y == 0.
+        {
+            a_vel.0.y = 0.;
+        }
+        // Actors keep to the level they are in, or the active one if they are outside of all,
+        // unless they are moving into another level.
+        let in_a_level = |point: Vec2| level_q.iter().any(|l_bounds| l_bounds.rect.contains(point));
+        let bounds = level_q.iter()
+            .map(|l_bounds| l_bounds.rect)
+            .find(|rect| rect.contains(a_transform.translation.truncate()))
+            .or(active_level.bounds);
+        if let Some(bounds) = bounds
+        {
+            let next = a_transform.translation.truncate()+motion;
+            if next.x+a_size.x/2. > bounds.max.x && !in_a_level(Vec2::new(next.x+a_size.x/2., next.y))
+            {
+                a_transform.translation.x = bounds.max.x-motion.x-a_size.x/2.;
+            }
+            if next.x-a_size.x/2. < bounds.min.x && !in_a_level(Vec2::new(next.x-a_size.x/2., next.y))
+            {
+                a_transform.translation.x = bounds.min.x-motion.x+a_size.x/2.;
+            }
+            if next.y+a_size.y/2. < bounds.min.y && !in_a_level(Vec2::new(next.x, next.y+a_size.y/2.))
+            {
+                match a_player
+                {
+                    Some(player_id) => died.send(PlayerDied { entity: a_entity, player_id: *player_id, cause: DeathCause::Fall }),
+                    None => commands.entity(a_entity).despawn()
+                }
+            }
+        }
+
+        a_transform.translation += motion.extend(0.);
+
+        if let Some(mut interpolated) = interpolated
+        {
+            interpolated.current = a_transform.translation;
+        }
+    }
+}
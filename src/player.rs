@@ -0,0 +1,301 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::abilities::{Abilities, FIRE_COOLDOWN, FIRE_SHOT};
+use crate::animation::{CharacterAnimator, PLAYER_ANIMATIONS_PATH};
+use crate::death::Dead;
+use crate::dialogue::no_dialogue;
+use crate::health::{Health, Invulnerable, PLAYER_MAX_HEALTH};
+use crate::input::{Action, PlayerId, PlayerInputs};
+use crate::ldtk_fields::{ldtk_fields, FromLdtkFields};
+use crate::level::PlayerSpawn;
+use crate::loading::PLAYER_TEXTURE;
+use crate::movement::{MovementConfig, MovementOverride};
+use crate::physics::{Actor, Vel};
+use crate::projectile::Shooter;
+
+#[derive(Component, Default)]
+pub struct Player;
+
+#[derive(Bundle)]
+pub struct PlayerBundle
+{
+    pub player: Player,
+    pub player_id: PlayerId,
+    pub vel: Vel,
+    pub actor: Actor,
+    pub health: Health,
+    pub abilities: Abilities,
+    pub shooter: Shooter,
+    pub animator: CharacterAnimator,
+    pub sprite_bundle: SpriteBundle,
+    pub worldly: Worldly
+}
+
+/// What a character's legs are doing, picking their animation.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LegState
+{
+    Idle,
+    Running,
+    Airborne
+}
+
+/// What a character's upper body is doing, picking its animation.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BodyState
+{
+    Idle,
+    Running,
+    Airborne,
+    Shooting
+}
+
+impl PlayerBundle
+{
+    /// A player for the given slot. `entity_iid` is what keeps LDtk from spawning it twice.
+    pub fn new(player_id: PlayerId, entity_iid: String, asset_server: &AssetServer) -> PlayerBundle
+    {
+        PlayerBundle
+        {
+            player: Player,
+            player_id,
+            vel: Vel(Vec2::new(0.,0.)),
+            actor: Actor
+            {
+                grounded: false,
+                jumped: false,
+                coyote_time: 0.,
+                jump_count: 0,
+                jump_limit: 1
+            },
+            health: Health::new(PLAYER_MAX_HEALTH),
+            abilities: Abilities::default(),
+            shooter: Shooter::new(FIRE_SHOT, FIRE_COOLDOWN),
+            animator: CharacterAnimator::new(asset_server.load(PLAYER_ANIMATIONS_PATH)),
+            sprite_bundle: SpriteBundle{texture: asset_server.load(PLAYER_TEXTURE), sprite: Sprite { color: player_color(player_id), custom_size: Some(Vec2::new(16.,32.)), ..default()}, ..default()},
+            worldly: Worldly { entity_iid }
+        }
+    }
+}
+
+/// Tint telling the players apart. Player one keeps the sprite's own colors.
+pub fn player_color(player_id: PlayerId) -> Color
+{
+    match player_id.0
+    {
+        0 => Color::WHITE,
+        1 => Color::rgb_u8(120, 200, 255),
+        _ => Color::rgb_u8(255, 200, 120)
+    }
+}
+
+ldtk_fields! {
+    struct PlayerFields
+    {
+        /// Player slot, 0 for player one.
+        "PlayerId" => player_id: u8
+    }
+}
+
+impl LdtkEntity for PlayerBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        asset_server: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> PlayerBundle {
+
+        let player_id = PlayerId(PlayerFields::from_entity(entity_instance, layer_instance).player_id);
+        PlayerBundle::new(player_id, entity_instance.iid.to_owned(), asset_server)
+    }
+}
+
+/// What happens to a player who dies; see [crate::death].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RespawnRule
+{
+    /// Every player comes back on their own, as often as needed.
+    Individual,
+    /// Each respawn takes one of [LocalPlayers::lives]. Once none are left, dead players stay
+    /// out, and when everyone is out the level starts over with [LocalPlayers::starting_lives].
+    SharedLives
+}
+
+/// Which player slots are in the game and how they respawn.
+///
+/// Player one always comes from the LDtk `Player` entity. Further slots join by pressing
+/// [Action::Jump] and spawn next to a player who is already in; slots are fixed by [PlayerId],
+/// never by query order.
+#[derive(Resource)]
+pub struct LocalPlayers
+{
+    /// Number of slots in play.
+    pub count: u8,
+    pub max_players: u8,
+    pub respawn: RespawnRule,
+    pub lives: u32,
+    pub starting_lives: u32,
+    /// `Worldly` iid of every slot that has been spawned, reused when it respawns.
+    spawned: Vec<Option<String>>
+}
+
+impl Default for LocalPlayers
+{
+    fn default() -> Self
+    {
+        LocalPlayers { count: 1, max_players: 2, respawn: RespawnRule::Individual, lives: 3, starting_lives: 3, spawned: Vec::new() }
+    }
+}
+
+impl LocalPlayers
+{
+    /// Puts `count` slots back in play, e.g. when a save is loaded.
+    ///
+    /// Player one is remembered as `entity_iid`, so it spawns at the level's [PlayerSpawn] if
+    /// the level LDtk puts it in is not the one being entered.
+    pub fn resume(&mut self, count: u8, entity_iid: String)
+    {
+        self.count = count.clamp(1, self.max_players);
+        if self.spawned.is_empty()
+        {
+            self.spawned.resize(self.max_players as usize, None);
+        }
+        self.spawned[0] = Some(entity_iid);
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, SystemLabel)]
+pub enum PlayerLabel
+{
+    /// Player velocities are set from input after this.
+    Move
+}
+
+/// Spawns the players and moves each from its player slot's actions.
+///
+/// Input is read every frame and only sets velocities, which physics applies on its own ticks.
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<LocalPlayers>()
+            .register_ldtk_entity::<PlayerBundle>("Player")
+            .add_system(player_move.run_in_state(GameState::Gameplay).run_if(no_dialogue).label(PlayerLabel::Move))
+            .add_system(manage_player_slots.run_in_state(GameState::Gameplay));
+    }
+}
+
+pub fn player_move(mut player: Query<(&mut Vel, &mut Actor, &PlayerId, Option<&MovementOverride>, Option<&Invulnerable>), (With<Player>, Without<Dead>)>, inputs: Res<PlayerInputs>, movement: Res<MovementConfig>)
+{
+    for (mut p_vel, mut p_actor, p_id, p_overrides, p_invulnerable) in player.iter_mut()
+    {
+        let movement = movement.with(p_overrides);
+        if p_invulnerable.is_some_and(|invulnerable| invulnerable.knocked_back())
+        {
+            continue;
+        }
+        if let Some(input) = inputs.player(*p_id)
+        {
+            if input.pressed(Action::MoveRight)
+            {
+                p_vel.0.x = movement.run_speed;
+            }
+            else if input.pressed(Action::MoveLeft)
+            {
+                p_vel.0.x = -movement.run_speed;
+            }
+            else
+            {
+                p_vel.0.x = 0.;
+            }
+            if input.just_pressed(Action::Jump) && (p_actor.coyote_time > 0. || p_actor.jump_count > 0)
+            {
+                p_vel.0.y = movement.jump_impulse;
+                p_actor.jumped = true;
+                p_actor.jump_count -= 1;
+                p_actor.coyote_time = 0.;
+            }
+            if input.just_released(Action::Jump) && p_vel.0.y > movement.jump_cut && p_actor.jumped
+            {
+                p_vel.0.y = movement.jump_cut;
+            }
+        }
+    }
+}
+
+/// Lets new players join, and brings everyone back once all players are out of lives.
+pub fn manage_player_slots(
+    mut local_players: ResMut<LocalPlayers>,
+    inputs: Res<PlayerInputs>,
+    player_q: Query<(&PlayerId, &Transform, &Worldly), With<Player>>,
+    player_spawn_q: Query<&Transform, (With<PlayerSpawn>, Without<Player>)>,
+    world_q: Query<Entity, With<Handle<LdtkAsset>>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands)
+{
+    let local_players = &mut *local_players;
+    if local_players.spawned.len() < local_players.max_players as usize
+    {
+        local_players.spawned.resize(local_players.max_players as usize, None);
+    }
+    for (p_id, _, p_worldly) in player_q.iter()
+    {
+        if let Some(slot) = local_players.spawned.get_mut(p_id.0 as usize)
+        {
+            *slot = Some(p_worldly.entity_iid.to_owned());
+        }
+    }
+
+    let next_slot = PlayerId(local_players.count);
+    if local_players.count < local_players.max_players
+        && inputs.player(next_slot).is_some_and(|input| input.just_pressed(Action::Jump))
+    {
+        local_players.count += 1;
+    }
+
+    if let Ok(world) = world_q.get_single()
+    {
+        let teammate = player_q.iter().min_by_key(|(p_id, _, _)| p_id.0).map(|(_, p_transform, _)| p_transform.translation);
+        for id in (0..local_players.count).map(PlayerId)
+        {
+            if player_q.iter().any(|(p_id, _, _)| *p_id == id)
+            {
+                continue;
+            }
+            let (entity_iid, translation) = match local_players.spawned[id.0 as usize].to_owned()
+            {
+                // Player one is spawned by LDtk the first time.
+                None if id.0 == 0 => continue,
+                None => match teammate
+                {
+                    Some(translation) => (format!("player-slot-{}", id.0), translation),
+                    None => continue
+                },
+                // Out of lives: wait until the others are out too.
+                Some(_) if teammate.is_some() => continue,
+                Some(entity_iid) => match player_spawn_q.iter().next()
+                {
+                    Some(ps_transform) => (entity_iid, ps_transform.translation),
+                    None => continue
+                }
+            };
+            if local_players.spawned[id.0 as usize].is_some()
+            {
+                local_players.lives = local_players.starting_lives;
+            }
+            let mut player = PlayerBundle::new(id, entity_iid, &asset_server);
+            player.sprite_bundle.transform.translation = translation;
+            commands.entity(world).with_children(|parent| {
+                parent.spawn(player);
+            });
+        }
+    }
+}
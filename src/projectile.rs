@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::collision::{collide_tiles, CollisionGrid};
+use crate::death::DeathCause;
+use crate::health::Damage;
+use crate::interactables::Enemy;
+use crate::physics::{Interpolated, PHYSICS_TIMESTEP};
+
+/// What a [Shooter] fires.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectileSpec
+{
+    /// Pixels per second.
+    pub speed: f32,
+    /// Seconds before it burns out.
+    pub lifetime: f32,
+    pub damage: u32,
+    /// Pulls it down like actors, in pixels per second squared. 0 flies straight.
+    pub gravity: f32,
+    /// Whether solid tiles stop it. Otherwise it flies through walls.
+    pub collides_with_solids: bool,
+    pub size: Vec2,
+    pub color: Color
+}
+
+/// Something flying that deals [Damage] to [Enemy]s it touches.
+#[derive(Component)]
+pub struct Projectile
+{
+    /// Pixels per second.
+    pub vel: Vec2,
+    pub lifetime: Timer,
+    pub damage: u32,
+    pub gravity: f32,
+    pub collides_with_solids: bool,
+    /// Entity that fired it, which it never hits.
+    pub owner: Option<Entity>
+}
+
+/// An entity that fires projectiles, at most once per `cooldown`.
+#[derive(Component, Debug, Clone)]
+pub struct Shooter
+{
+    pub projectile: ProjectileSpec,
+    pub cooldown: Timer
+}
+
+impl Shooter
+{
+    /// A shooter that can fire right away and then every `cooldown` seconds.
+    pub fn new(projectile: ProjectileSpec, cooldown: f32) -> Shooter
+    {
+        let mut cooldown = Timer::from_seconds(cooldown, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        Shooter { projectile, cooldown }
+    }
+
+    pub fn ready(&self) -> bool
+    {
+        self.cooldown.finished()
+    }
+
+    /// Whether it is still cooling down from a shot, e.g. to show a shooting pose.
+    pub fn shooting(&self) -> bool
+    {
+        !self.cooldown.finished()
+    }
+
+    /// Fires at `translation` towards `direction` if the cooldown allows it.
+    ///
+    /// `parent` is the entity the projectile is spawned under, usually the level, so it goes
+    /// away with it. Returns whether a projectile was spawned.
+    pub fn fire(
+        &mut self,
+        commands: &mut Commands,
+        parent: Option<Entity>,
+        translation: Vec3,
+        direction: Vec2,
+        owner: Entity) -> bool
+    {
+        if !self.ready()
+        {
+            return false;
+        }
+        self.cooldown.reset();
+        spawn_projectile(commands, parent, &self.projectile, translation, direction, Some(owner));
+        true
+    }
+}
+
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    parent: Option<Entity>,
+    spec: &ProjectileSpec,
+    translation: Vec3,
+    direction: Vec2,
+    owner: Option<Entity>)
+{
+    let projectile = (
+        Projectile {
+            vel: direction.normalize_or_zero()*spec.speed,
+            lifetime: Timer::from_seconds(spec.lifetime, TimerMode::Once),
+            damage: spec.damage,
+            gravity: spec.gravity,
+            collides_with_solids: spec.collides_with_solids,
+            owner
+        },
+        SpriteBundle {
+            sprite: Sprite { color: spec.color, custom_size: Some(spec.size), ..default() },
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        Interpolated { previous: translation, current: translation }
+    );
+    match parent
+    {
+        Some(parent) => { commands.entity(parent).with_children(|parent| { parent.spawn(projectile); }); },
+        None => { commands.spawn(projectile); }
+    }
+}
+
+/// Moves [Projectile]s on the physics timestep and lets them hit walls and enemies.
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_system(tick_shooters)
+            .add_fixed_timestep_system(PHYSICS_TIMESTEP, 0, projectile_physics.run_in_state(GameState::Gameplay));
+    }
+}
+
+pub fn tick_shooters(mut shooter_q: Query<&mut Shooter>, time: Res<Time>)
+{
+    for mut shooter in shooter_q.iter_mut()
+    {
+        shooter.cooldown.tick(time.delta());
+    }
+}
+
+pub fn projectile_physics(
+    mut projectile_q: Query<(&mut Projectile, &mut Transform, &mut Interpolated, &Sprite, Entity)>,
+    enemy_q: Query<(&Transform, &Enemy, Entity), Without<Projectile>>,
+    timesteps: Res<FixedTimesteps>,
+    grid: Res<CollisionGrid>,
+    mut damage: EventWriter<Damage>,
+    mut commands: Commands)
+{
+    let dt = timesteps.current().timestep().as_secs_f32();
+    let mut hit_enemies = Vec::new();
+    for (mut projectile, mut p_transform, mut interpolated, p_sprite, p_entity) in projectile_q.iter_mut()
+    {
+        interpolated.previous = p_transform.translation;
+        projectile.lifetime.tick(timesteps.current().timestep());
+        projectile.vel.y -= projectile.gravity*dt;
+
+        let p_size = p_sprite.custom_size.unwrap();
+        let mut motion = projectile.vel*dt;
+        let mut spent = projectile.lifetime.finished();
+        if projectile.collides_with_solids
+        {
+            collide_tiles(&mut p_transform.translation, &mut motion, p_size, |center, size| grid.solids_in(center, size));
+            // A blocked axis means a wall was hit.
+            spent |= (motion.x == 0. && projectile.vel.x != 0.) || (motion.y == 0. && projectile.vel.y != 0.);
+        }
+        p_transform.translation += motion.extend(0.);
+        interpolated.current = p_transform.translation;
+
+        for (e_transform, e_stats, e_entity) in enemy_q.iter()
+        {
+            if !spent && e_stats.tangible && projectile.damage > 0
+                && Some(e_entity) != projectile.owner
+                && !hit_enemies.contains(&e_entity)
+                && collide(
+                p_transform.translation,
+                p_size,
+                e_transform.translation,
+                Vec2::splat(32.)
+            ).is_some()
+            {
+                damage.send(Damage {
+                    entity: e_entity,
+                    amount: projectile.damage,
+                    source: DeathCause::Projectile,
+                    knockback: Vec2::ZERO
+                });
+                hit_enemies.push(e_entity);
+                spent = true;
+            }
+        }
+        if spent
+        {
+            commands.entity(p_entity).despawn();
+        }
+    }
+}
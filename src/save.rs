@@ -0,0 +1,559 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GameState;
+use crate::abilities::Abilities;
+use crate::health::Health;
+use crate::input::PlayerId;
+use crate::interactables::{Checkpoint, LitCheckpoints};
+use crate::level::ActiveLevel;
+use crate::physics::{Actor, Interpolated, Vel};
+use crate::player::{LocalPlayers, Player};
+use crate::score::Wallet;
+use crate::triggers::GameFlags;
+
+/// Version written into new saves. Bump it whenever [SaveData] changes shape.
+pub const SAVE_VERSION: u32 = 1;
+/// Number of save slots offered.
+pub const SAVE_SLOTS: u8 = 3;
+/// Folder inside the platform's data directory that saves go in.
+pub const SAVE_DIRECTORY: &str = "pillars_of_nature";
+
+/// Everything about a game in progress that outlives a session.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct SaveData
+{
+    pub version: u32,
+    /// LDtk identifier of the level to resume in.
+    pub level: String,
+    pub players: Vec<PlayerSave>,
+    /// Lit checkpoint id by level uid, as in [LitCheckpoints].
+    pub checkpoints: HashMap<i32, u8>,
+    pub latest_checkpoint: Option<(i32, u8)>,
+    pub coins: u32,
+    /// Collected coin iids by level iid, as in [Wallet].
+    pub collected_coins: HashMap<String, HashSet<String>>,
+    pub flags: HashSet<String>
+}
+
+/// The part of a player that is kept: their powers and what they add to the jump.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PlayerSave
+{
+    pub id: u8,
+    /// `Worldly` iid of the player entity.
+    pub entity_iid: String,
+    pub fire: bool,
+    pub earth: bool,
+    pub water: bool,
+    pub air: bool,
+    pub jump_limit: u8
+}
+
+/// Only the version, read first so saves from a newer game are turned away before parsing.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SaveHeader
+{
+    version: u32
+}
+
+#[derive(Debug)]
+pub enum SaveError
+{
+    /// No data directory could be found, or saving was turned off.
+    NoDirectory,
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Write(ron::Error),
+    /// The save was written by a newer version of the game.
+    Newer(u32)
+}
+
+impl fmt::Display for SaveError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            SaveError::NoDirectory => write!(f, "saving is disabled"),
+            SaveError::Io(error) => write!(f, "{}", error),
+            SaveError::Parse(error) => write!(f, "{}", error),
+            SaveError::Write(error) => write!(f, "{}", error),
+            SaveError::Newer(version) => write!(f, "save version {} is newer than {}", version, SAVE_VERSION)
+        }
+    }
+}
+
+impl From<io::Error> for SaveError
+{
+    fn from(error: io::Error) -> Self
+    {
+        SaveError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError
+{
+    fn from(error: ron::error::SpannedError) -> Self
+    {
+        SaveError::Parse(error)
+    }
+}
+
+impl From<ron::Error> for SaveError
+{
+    fn from(error: ron::Error) -> Self
+    {
+        SaveError::Write(error)
+    }
+}
+
+impl SaveData
+{
+    /// Reads a save, upgrading it if an older version of the game wrote it.
+    pub fn from_ron(text: &str) -> Result<SaveData, SaveError>
+    {
+        let header: SaveHeader = ron::from_str(text)?;
+        if header.version > SAVE_VERSION
+        {
+            return Err(SaveError::Newer(header.version));
+        }
+        let data: SaveData = ron::from_str(text)?;
+        Ok(data.migrate())
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError>
+    {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    /// Brings a save up to [SAVE_VERSION] one version at a time.
+    ///
+    /// Fields that were only added are filled in by their defaults when the file is read.
+    /// Changes in meaning get a step here, matching on the version they upgrade from.
+    fn migrate(mut self) -> SaveData
+    {
+        while self.version < SAVE_VERSION
+        {
+            // Version 0 is a save from before versioning, which matches version 1.
+            self.version += 1;
+        }
+        self
+    }
+}
+
+/// `pillars_of_nature` in the platform's data directory, if one can be found.
+pub fn default_save_directory() -> Option<PathBuf>
+{
+    let base = if cfg!(target_os = "windows")
+    {
+        env::var_os("APPDATA").map(PathBuf::from)
+    }
+    else if cfg!(target_os = "macos")
+    {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    }
+    else
+    {
+        env::var_os("XDG_DATA_HOME")
+            .filter(|data_home| !data_home.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.map(|base| base.join(SAVE_DIRECTORY))
+}
+
+/// Where saves live and which slot the game in progress autosaves to.
+#[derive(Resource, Debug, Clone)]
+pub struct SaveSlots
+{
+    /// `None` turns saving and loading off.
+    pub directory: Option<PathBuf>,
+    pub active: u8
+}
+
+impl Default for SaveSlots
+{
+    fn default() -> Self
+    {
+        SaveSlots { directory: default_save_directory(), active: 0 }
+    }
+}
+
+impl SaveSlots
+{
+    pub fn path(&self, slot: u8) -> Option<PathBuf>
+    {
+        self.directory.as_ref().map(|directory| directory.join(format!("slot_{}.ron", slot)))
+    }
+
+    /// Where the [Settings](crate::settings::Settings) are kept, next to the slots.
+    pub fn settings_path(&self) -> Option<PathBuf>
+    {
+        self.directory.as_ref().map(|directory| directory.join("settings.ron"))
+    }
+
+    pub fn exists(&self, slot: u8) -> bool
+    {
+        self.path(slot).is_some_and(|path| path.is_file())
+    }
+
+    pub fn read(&self, slot: u8) -> Result<SaveData, SaveError>
+    {
+        let path = self.path(slot).ok_or(SaveError::NoDirectory)?;
+        SaveData::from_ron(&fs::read_to_string(path)?)
+    }
+
+    /// Writes next to the slot first, so a crash midway leaves the old save intact.
+    pub fn write(&self, slot: u8, data: &SaveData) -> Result<(), SaveError>
+    {
+        let path = self.path(slot).ok_or(SaveError::NoDirectory)?;
+        if let Some(directory) = path.parent()
+        {
+            fs::create_dir_all(directory)?;
+        }
+        let temp = path.with_extension("ron.tmp");
+        fs::write(&temp, data.to_ron()?)?;
+        fs::rename(temp, path)?;
+        Ok(())
+    }
+}
+
+/// Send to write the game in progress to a slot.
+#[derive(Debug, Clone, Copy)]
+pub struct SaveGame
+{
+    pub slot: u8
+}
+
+/// Send to continue from a slot, which also becomes the one autosaves go to.
+#[derive(Debug, Clone, Copy)]
+pub struct LoadGame
+{
+    pub slot: u8
+}
+
+/// Bookkeeping between a [LoadGame] and the players it restores, and for autosaves.
+#[derive(Resource, Default)]
+pub struct SaveState
+{
+    /// Identifier of the active level.
+    pub level: Option<String>,
+    /// A loaded save that not every player has been restored from yet.
+    pub restoring: Option<SaveData>,
+    /// Whether the level of `restoring` is in, so players can be placed in it.
+    pub level_ready: bool,
+    restored: Vec<PlayerId>,
+    /// Set when a checkpoint is lit or a level entered; written once play goes on.
+    pub autosave_pending: bool
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, SystemLabel)]
+pub enum SaveLabel
+{
+    /// [SaveGame] events sent before this are written the same frame.
+    Write
+}
+
+/// Keeps progress in versioned RON saves under the platform's data directory.
+///
+/// Whichever slot was last loaded or started in is autosaved whenever a checkpoint is lit or
+/// a level is entered. Loading restores the level, checkpoints, coins,
+/// flags and each player's powers, and puts players at the latest checkpoint if it is in
+/// that level.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_event::<SaveGame>()
+            .add_event::<LoadGame>()
+            .init_resource::<SaveSlots>()
+            .init_resource::<SaveState>()
+            .add_system(load_game)
+            .add_system(track_progress)
+            .add_system(restore_players.run_in_state(GameState::Gameplay))
+            .add_system(save_game.label(SaveLabel::Write));
+    }
+}
+
+pub fn load_game(
+    mut load: EventReader<LoadGame>,
+    mut slots: ResMut<SaveSlots>,
+    mut save_state: ResMut<SaveState>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut lit_checkpoints: ResMut<LitCheckpoints>,
+    mut wallet: ResMut<Wallet>,
+    mut flags: ResMut<GameFlags>,
+    mut local_players: ResMut<LocalPlayers>,
+    state: Res<CurrentState<GameState>>,
+    mut commands: Commands)
+{
+    for event in load.iter()
+    {
+        let data = match slots.read(event.slot)
+        {
+            Ok(data) => data,
+            Err(error) =>
+            {
+                warn!("Could not load save slot {}: {}", event.slot, error);
+                continue;
+            }
+        };
+        slots.active = event.slot;
+
+        lit_checkpoints.per_level = data.checkpoints.to_owned();
+        lit_checkpoints.latest = data.latest_checkpoint;
+        wallet.coins = data.coins;
+        wallet.collected = data.collected_coins.to_owned();
+        flags.0 = data.flags.to_owned();
+        if let Some(first) = data.players.iter().find(|player| player.id == 0)
+        {
+            let count = data.players.iter().map(|player| player.id+1).max().unwrap_or(1);
+            local_players.resume(count, first.entity_iid.to_owned());
+        }
+
+        // The same level is not spawned again, so players can be placed right away.
+        save_state.level_ready = save_state.level.as_ref() == Some(&data.level);
+        if !data.level.is_empty() && !save_state.level_ready
+        {
+            *level_selection = LevelSelection::Identifier(data.level.to_owned());
+            if state.0 == GameState::Gameplay
+            {
+                commands.insert_resource(NextState(GameState::MapLoad));
+            }
+        }
+        save_state.restored.clear();
+        save_state.restoring = Some(data);
+    }
+}
+
+/// Notes the level that was entered and anything since that calls for an autosave.
+pub fn track_progress(
+    active_level: Res<ActiveLevel>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    lit_checkpoints: Res<LitCheckpoints>,
+    mut save_state: ResMut<SaveState>)
+{
+    if active_level.is_changed()
+    {
+        if let Some(iid) = &active_level.iid
+        {
+            if let Some(level) = ldtk_levels.iter().map(|(_, ldtk_level)| &ldtk_level.level).find(|level| level.iid == *iid)
+            {
+                save_state.level = Some(level.identifier.to_owned());
+                save_state.level_ready = true;
+                save_state.autosave_pending = true;
+            }
+        }
+    }
+    if lit_checkpoints.is_changed()
+    {
+        save_state.autosave_pending = true;
+    }
+}
+
+/// Hands each player their saved powers and moves them to the latest checkpoint.
+pub fn restore_players(
+    mut player_q: Query<(&PlayerId, &mut Actor, &mut Abilities, &mut Health, &mut Vel, &mut Transform, Option<&mut Interpolated>), With<Player>>,
+    checkpoint_q: Query<(&Transform, &Checkpoint), Without<Player>>,
+    mut save_state: ResMut<SaveState>)
+{
+    let save_state = &mut *save_state;
+    if !save_state.level_ready
+    {
+        return;
+    }
+    let data = match &save_state.restoring
+    {
+        Some(data) => data,
+        None => return
+    };
+    let resume_point = checkpoint_q.iter()
+        .find(|(_, checkpoint)| data.latest_checkpoint == Some((checkpoint.level_uid, checkpoint.id)))
+        .map(|(c_transform, _)| c_transform.translation);
+
+    for (p_id, mut p_actor, mut p_abilities, mut p_health, mut p_vel, mut p_transform, p_interpolated) in player_q.iter_mut()
+    {
+        if save_state.restored.contains(p_id)
+        {
+            continue;
+        }
+        if let Some(saved) = data.players.iter().find(|player| player.id == p_id.0)
+        {
+            p_abilities.fire = saved.fire;
+            p_abilities.earth = saved.earth;
+            p_abilities.water = saved.water;
+            p_abilities.air = saved.air;
+            p_actor.jump_limit = saved.jump_limit;
+            p_actor.jump_count = p_actor.jump_count.min(saved.jump_limit);
+        }
+        p_health.current = p_health.max;
+        p_vel.0 = Vec2::ZERO;
+        if let Some(resume_point) = resume_point
+        {
+            p_transform.translation.x = resume_point.x;
+            p_transform.translation.y = resume_point.y;
+            if let Some(mut interpolated) = p_interpolated
+            {
+                interpolated.previous = p_transform.translation;
+                interpolated.current = p_transform.translation;
+            }
+        }
+        save_state.restored.push(*p_id);
+    }
+
+    if data.players.iter().all(|player| save_state.restored.contains(&PlayerId(player.id)))
+    {
+        save_state.restoring = None;
+    }
+}
+
+/// Writes [SaveGame] requests, and autosaves to the active slot during play once a load has
+/// settled.
+pub fn save_game(
+    mut save: EventReader<SaveGame>,
+    player_q: Query<(&PlayerId, &Actor, &Abilities, &Worldly), With<Player>>,
+    slots: Res<SaveSlots>,
+    lit_checkpoints: Res<LitCheckpoints>,
+    wallet: Res<Wallet>,
+    flags: Res<GameFlags>,
+    mut save_state: ResMut<SaveState>,
+    state: Res<CurrentState<GameState>>)
+{
+    let mut targets: Vec<u8> = save.iter().map(|event| event.slot).collect();
+    if save_state.autosave_pending && state.0 == GameState::Gameplay && save_state.restoring.is_none() && !player_q.is_empty()
+    {
+        save_state.autosave_pending = false;
+        targets.push(slots.active);
+    }
+    if targets.is_empty() || slots.directory.is_none()
+    {
+        return;
+    }
+    let level = match &save_state.level
+    {
+        Some(level) => level.to_owned(),
+        None => return
+    };
+
+    let mut players: Vec<PlayerSave> = player_q.iter().map(|(p_id, p_actor, p_abilities, p_worldly)| PlayerSave {
+        id: p_id.0,
+        entity_iid: p_worldly.entity_iid.to_owned(),
+        fire: p_abilities.fire,
+        earth: p_abilities.earth,
+        water: p_abilities.water,
+        air: p_abilities.air,
+        jump_limit: p_actor.jump_limit
+    }).collect();
+    players.sort_by_key(|player| player.id);
+    let data = SaveData {
+        version: SAVE_VERSION,
+        level,
+        players,
+        checkpoints: lit_checkpoints.per_level.to_owned(),
+        latest_checkpoint: lit_checkpoints.latest,
+        coins: wallet.coins,
+        collected_coins: wallet.collected.to_owned(),
+        flags: flags.0.to_owned()
+    };
+
+    targets.sort();
+    targets.dedup();
+    for slot in targets
+    {
+        if let Err(error) = slots.write(slot, &data)
+        {
+            warn!("Could not write save slot {}: {}", slot, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn save() -> SaveData
+    {
+        SaveData
+        {
+            version: SAVE_VERSION,
+            level: "Home".to_string(),
+            players: vec![PlayerSave { id: 0, entity_iid: "player".to_string(), fire: true, water: true, jump_limit: 2, ..default() }],
+            checkpoints: HashMap::from_iter([(3, 1), (7, 0)]),
+            latest_checkpoint: Some((7, 0)),
+            coins: 12,
+            collected_coins: HashMap::from_iter([("yard".to_string(), HashSet::from_iter(["coin".to_string()]))]),
+            flags: HashSet::from_iter(["gate_open".to_string()])
+        }
+    }
+
+    #[test]
+    fn saves_read_back_what_was_written()
+    {
+        let data = save();
+        assert_eq!(SaveData::from_ron(&data.to_ron().unwrap()).unwrap(), data);
+    }
+
+    #[test]
+    fn saves_from_before_versioning_are_migrated()
+    {
+        let text = r#"(
+            level: "Home",
+            players: [(id: 0, entity_iid: "player", fire: true, water: true, jump_limit: 2)],
+            checkpoints: {3: 1, 7: 0},
+            latest_checkpoint: Some((7, 0)),
+            coins: 12,
+            collected_coins: {"yard": ["coin"]},
+            flags: ["gate_open"],
+        )"#;
+        let data = SaveData::from_ron(text).unwrap();
+        assert_eq!(data.version, SAVE_VERSION);
+        assert_eq!(data, save());
+    }
+
+    #[test]
+    fn saves_from_a_newer_game_are_rejected()
+    {
+        let text = save().to_ron().unwrap();
+        let newer = text.replacen(&format!("version: {}", SAVE_VERSION), &format!("version: {}", SAVE_VERSION+1), 1);
+        assert!(matches!(SaveData::from_ron(&newer), Err(SaveError::Newer(version)) if version == SAVE_VERSION+1));
+    }
+
+    #[test]
+    fn broken_saves_are_parse_errors()
+    {
+        assert!(matches!(SaveData::from_ron("(version: 1, coins: \"many\")"), Err(SaveError::Parse(_))));
+        assert!(matches!(SaveData::from_ron("not a save"), Err(SaveError::Parse(_))));
+    }
+
+    #[test]
+    fn slots_write_and_read_through_the_directory()
+    {
+        let directory = env::temp_dir().join(format!("pillars_of_nature_save_test_{}", std::process::id()));
+        let slots = SaveSlots { directory: Some(directory.clone()), active: 0 };
+        assert!(!slots.exists(1));
+        slots.write(1, &save()).unwrap();
+        assert!(slots.exists(1));
+        assert_eq!(slots.read(1).unwrap(), save());
+        fs::remove_dir_all(directory).unwrap();
+
+        let disabled = SaveSlots { directory: None, active: 0 };
+        assert!(matches!(disabled.read(0), Err(SaveError::NoDirectory)));
+        assert!(matches!(disabled.write(0, &save()), Err(SaveError::NoDirectory)));
+    }
+}
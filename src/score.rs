@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use iyes_loopless::prelude::*;
+
+use crate::in_game;
+use crate::input::PlayerId;
+use crate::interactables::Coin;
+use crate::loading::FontAssets;
+
+/// Sent when a player picks up a [Coin].
+#[derive(Debug, Clone)]
+pub struct CoinCollected
+{
+    /// LDtk iid of the coin.
+    pub iid: String,
+    /// LDtk iid of the level it was in.
+    pub level_iid: String,
+    pub player_id: PlayerId
+}
+
+/// Coins picked up so far, shared by all players.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct Wallet
+{
+    pub coins: u32,
+    /// Iids of the collected coins, by the iid of their level.
+    pub collected: HashMap<String, HashSet<String>>
+}
+
+impl Wallet
+{
+    pub fn is_collected(&self, iid: &str) -> bool
+    {
+        self.collected.values().any(|coins| coins.contains(iid))
+    }
+
+    /// How many coins were collected in one level.
+    pub fn collected_in(&self, level_iid: &str) -> usize
+    {
+        self.collected.get(level_iid).map_or(0, |coins| coins.len())
+    }
+}
+
+#[derive(Component)]
+pub struct CoinCounter;
+
+/// Counts [CoinCollected] into the [Wallet] and keeps collected coins from coming back.
+///
+/// Levels are spawned anew when entered through a door, so coins already in the [Wallet]
+/// are removed as soon as they appear. The count is shown in the top right corner while
+/// [in_game].
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_event::<CoinCollected>()
+            .init_resource::<Wallet>()
+            .add_system(collect_coins)
+            .add_system(remove_collected_coins)
+            .add_system_to_stage(CoreStage::PostUpdate, draw_coin_counter.run_if(in_game));
+    }
+}
+
+pub fn collect_coins(mut collected: EventReader<CoinCollected>, mut wallet: ResMut<Wallet>)
+{
+    for coin in collected.iter()
+    {
+        if wallet.collected.entry(coin.level_iid.to_owned()).or_default().insert(coin.iid.to_owned())
+        {
+            wallet.coins += 1;
+        }
+    }
+}
+
+pub fn remove_collected_coins(coin_q: Query<(&Coin, Entity), Added<Coin>>, wallet: Res<Wallet>, mut commands: Commands)
+{
+    for (c_coin, c_entity) in coin_q.iter()
+    {
+        if wallet.is_collected(&c_coin.iid)
+        {
+            commands.entity(c_entity).despawn();
+        }
+    }
+}
+
+pub fn draw_coin_counter(
+    wallet: Res<Wallet>,
+    mut counter_q: Query<&mut Text, With<CoinCounter>>,
+    fonts: Res<FontAssets>,
+    mut commands: Commands)
+{
+    let label = format!("Coins: {}", wallet.coins);
+    match counter_q.get_single_mut()
+    {
+        Ok(mut text) =>
+        {
+            if wallet.is_changed()
+            {
+                text.sections[0].value = label;
+            }
+        },
+        Err(_) =>
+        {
+            commands.spawn((TextBundle::from_section(label, TextStyle {
+                font: fonts.main.clone(),
+                font_size: 24.0,
+                color: Color::rgb_u8(255, 200, 80)
+            }).with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { right: Val::Px(16.), top: Val::Px(16.), ..default() },
+                ..default()
+            }), CoinCounter));
+        }
+    }
+}
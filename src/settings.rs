@@ -0,0 +1,126 @@
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::input::{InputBindings, InputBindingsHandle, INPUT_BINDINGS_PATH};
+use crate::save::{SaveError, SaveSlots};
+
+/// Smallest and largest window scale offered.
+pub const WINDOW_SCALES: (f32, f32) = (1., 3.);
+
+/// Player preferences, kept in `settings.ron` next to the save slots.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings
+{
+    /// 0 to 1, applied to every sound played.
+    pub volume: f32,
+    /// How many screen pixels one window pixel takes up.
+    pub window_scale: f32,
+    /// Bindings changed in the settings screen. `None` uses [INPUT_BINDINGS_PATH].
+    pub bindings: Option<InputBindings>
+}
+
+impl Default for Settings
+{
+    fn default() -> Self
+    {
+        Settings { volume: 1., window_scale: 1., bindings: None }
+    }
+}
+
+impl Settings
+{
+    pub fn read(slots: &SaveSlots) -> Result<Settings, SaveError>
+    {
+        let path = slots.settings_path().ok_or(SaveError::NoDirectory)?;
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn write(&self, slots: &SaveSlots) -> Result<(), SaveError>
+    {
+        let path = slots.settings_path().ok_or(SaveError::NoDirectory)?;
+        if let Some(directory) = path.parent()
+        {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)?;
+        Ok(())
+    }
+}
+
+/// Reads the [Settings] on start, applies them and writes them back whenever they change.
+///
+/// Custom bindings replace the [InputBindings] asset, which then stops being applied until
+/// they are reset.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<Settings>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, load_settings)
+            .add_system(apply_settings)
+            .add_system_to_stage(CoreStage::Last, store_settings);
+    }
+}
+
+pub fn load_settings(slots: Res<SaveSlots>, mut settings: ResMut<Settings>, mut commands: Commands)
+{
+    if slots.settings_path().is_some_and(|path| path.is_file())
+    {
+        match Settings::read(&slots)
+        {
+            Ok(read) => *settings = read,
+            Err(error) => warn!("Could not read settings: {}", error)
+        }
+    }
+    if let Some(bindings) = &settings.bindings
+    {
+        commands.insert_resource(bindings.clone());
+        commands.remove_resource::<InputBindingsHandle>();
+    }
+}
+
+/// Drops custom bindings and goes back to the ones in [INPUT_BINDINGS_PATH].
+pub fn reset_bindings(
+    settings: &mut Settings,
+    bindings: &mut InputBindings,
+    assets: &Assets<InputBindings>,
+    asset_server: &AssetServer,
+    commands: &mut Commands)
+{
+    settings.bindings = None;
+    let handle = asset_server.load(INPUT_BINDINGS_PATH);
+    *bindings = assets.get(&handle).cloned().unwrap_or_default();
+    commands.insert_resource(InputBindingsHandle(handle));
+}
+
+pub fn apply_settings(settings: Res<Settings>, windows: Option<ResMut<Windows>>)
+{
+    if !settings.is_changed()
+    {
+        return;
+    }
+    if let Some(window) = windows.and_then(|windows| windows.into_inner().get_primary_mut())
+    {
+        let scale = settings.window_scale.clamp(WINDOW_SCALES.0, WINDOW_SCALES.1) as f64;
+        if window.scale_factor_override() != Some(scale)
+        {
+            window.set_scale_factor_override(Some(scale));
+        }
+    }
+}
+
+pub fn store_settings(settings: Res<Settings>, slots: Res<SaveSlots>)
+{
+    if settings.is_changed() && !settings.is_added() && slots.directory.is_some()
+    {
+        if let Err(error) = settings.write(&slots)
+        {
+            warn!("Could not write settings: {}", error);
+        }
+    }
+}
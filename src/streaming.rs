@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::death::Dead;
+use crate::level::{ActiveLevel, LevelBounds};
+use crate::player::Player;
+
+/// Whether levels are laid out like in the LDtk world, with the active level's neighbours
+/// loaded around it.
+///
+/// Off by default, so only the selected level is loaded and every level sits at the origin:
+/// the levels are made to be entered through doors, and walking off their edges leads into
+/// unfinished ones like `PreFinale`. Start the game with `--streaming` to turn it on. Only
+/// applies to levels spawned after it changes, so insert it before the game starts.
+#[derive(Resource, Default)]
+pub struct WorldStreaming(pub bool);
+
+/// Lets players walk from one level into the next without a door, once [WorldStreaming] is on.
+///
+/// The level they walk into becomes the selected one, which loads its neighbours and unloads
+/// the levels that are no longer next to it.
+pub struct StreamingPlugin;
+
+impl Plugin for StreamingPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<WorldStreaming>()
+            .add_system(apply_world_streaming)
+            .add_system(follow_players.run_in_state(GameState::Gameplay));
+    }
+}
+
+pub fn apply_world_streaming(streaming: Res<WorldStreaming>, mut ldtk_settings: ResMut<LdtkSettings>)
+{
+    if !streaming.is_changed()
+    {
+        return;
+    }
+    ldtk_settings.level_spawn_behavior = if streaming.0
+    {
+        LevelSpawnBehavior::UseWorldTranslation { load_level_neighbors: true }
+    }
+    else
+    {
+        LevelSpawnBehavior::UseZeroTranslation
+    };
+}
+
+/// Selects the level the players are in once they have left the active one.
+pub fn follow_players(
+    streaming: Res<WorldStreaming>,
+    player_q: Query<&Transform, (With<Player>, Without<Dead>)>,
+    level_q: Query<&LevelBounds>,
+    active_level: Res<ActiveLevel>,
+    level_selection: Res<LevelSelection>,
+    mut commands: Commands)
+{
+    if !streaming.0 || player_q.is_empty()
+    {
+        return;
+    }
+    // Where the camera looks, so the view and the loaded levels move together.
+    let center = player_q.iter().map(|p_transform| p_transform.translation.truncate()).sum::<Vec2>()/player_q.iter().len() as f32;
+    if active_level.bounds.is_some_and(|bounds| bounds.contains(center))
+    {
+        return;
+    }
+    if let Some(l_bounds) = level_q.iter().find(|l_bounds| l_bounds.rect.contains(center))
+    {
+        let selection = LevelSelection::Iid(l_bounds.iid.to_owned());
+        if *level_selection != selection && active_level.iid.as_ref() != Some(&l_bounds.iid)
+        {
+            commands.insert_resource(selection);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::GameState;
+use crate::ldtk_fields::{ldtk_fields, FromLdtkFields};
+use crate::loading::FONT_PATH;
+
+ldtk_fields! {
+    #[derive(Component, Default)]
+    pub struct InGameText
+    {
+        "Value" => pub value: String,
+        "Visible" => pub visible: bool,
+        /// Shared with the triggers that show and hide it.
+        "TextID" => pub text_id: u32 = 666
+    }
+}
+
+#[derive(Bundle)]
+pub struct InGameTextBundle
+{
+    pub text_bundle: Text2dBundle,
+    pub text: InGameText
+}
+
+impl LdtkEntity for InGameTextBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        asset_server: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> InGameTextBundle {
+
+        let text = InGameText::from_entity(entity_instance, layer_instance);
+
+        InGameTextBundle {
+            text_bundle: Text2dBundle{
+                text: Text::from_section(text.value.clone(), TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                }).with_alignment(TextAlignment::CENTER),
+                visibility: Visibility { is_visible: text.visible },
+                ..default()
+            },
+            text
+        }
+    }
+}
+
+/// In-level text entities and their visibility.
+pub struct TextPlugin;
+
+impl Plugin for TextPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.register_ldtk_entity::<InGameTextBundle>("TextEntity")
+            .add_system(text.run_in_state(GameState::Gameplay));
+    }
+}
+
+pub fn text(mut text_q: Query<(&mut Visibility, &InGameText), With<InGameText>>)
+{
+    for (mut tx_visible, tx_text) in text_q.iter_mut()
+    {
+        tx_visible.is_visible = tx_text.visible;
+    }
+}
@@ -0,0 +1,331 @@
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use bevy::utils::HashSet;
+use bevy_ecs_ldtk::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{GameState, TILE_SIZE};
+use crate::death::Dead;
+use crate::interactables::{Door, Enemy, EnemyBundle};
+use crate::ldtk_fields::{ldtk_fields, FromLdtkFields};
+use crate::loading::AudioAssets;
+use crate::movement::MovementOverride;
+use crate::player::Player;
+use crate::settings::Settings;
+use crate::text::InGameText;
+
+/// Something a [Trigger] does when it fires.
+///
+/// Written in the trigger's `Actions` field in LDtk, one per line, as a verb and its argument:
+/// `show_text 1`, `hide_text 1`, `open_door Home`, `spawn_enemy 2 0` (offset in tiles),
+/// `play_sound bell.ogg` or `set_flag met_guide`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerAction
+{
+    /// Shows every [InGameText] with this text id.
+    ShowText(u32),
+    /// Hides every [InGameText] with this text id.
+    HideText(u32),
+    /// Unlocks the doors leading to this level.
+    OpenDoor(String),
+    /// Spawns an enemy this many tiles from the trigger.
+    SpawnEnemy(Vec2),
+    /// Plays the sound at this asset path.
+    PlaySound(String),
+    /// Adds this name to the [GameFlags].
+    SetFlag(String)
+}
+
+impl TriggerAction
+{
+    pub fn parse(action: &str) -> Option<TriggerAction>
+    {
+        let mut words = action.split_whitespace();
+        let verb = words.next()?;
+        let arg = words.next();
+        match verb
+        {
+            "show_text" => arg?.parse().ok().map(TriggerAction::ShowText),
+            "hide_text" => arg?.parse().ok().map(TriggerAction::HideText),
+            "open_door" => Some(TriggerAction::OpenDoor(arg?.to_string())),
+            "spawn_enemy" =>
+            {
+                let x = arg.map_or(Some(0.), |x| x.parse().ok())?;
+                let y = words.next().map_or(Some(0.), |y| y.parse().ok())?;
+                Some(TriggerAction::SpawnEnemy(Vec2::new(x, y)))
+            },
+            "play_sound" => Some(TriggerAction::PlaySound(arg?.to_string())),
+            "set_flag" => Some(TriggerAction::SetFlag(arg?.to_string())),
+            _ => None
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct Trigger{
+    pub id: u32,
+    pub visible: bool,
+    pub actions: Vec<TriggerAction>,
+    /// Fires only the first time.
+    pub once: bool,
+    /// Fires when the last player leaves instead of when the first one enters.
+    pub on_exit: bool,
+    /// Whether a player is inside.
+    pub occupied: bool,
+    pub fired: bool
+}
+
+#[derive(Bundle)]
+pub struct TriggerBundle
+{
+    pub trigger: Trigger,
+    pub sprite: SpriteBundle
+}
+
+ldtk_fields! {
+    struct TriggerFields
+    {
+        "ID" => id: u32 = 666,
+        "Visible" => visible: bool,
+        /// One [TriggerAction] per entry.
+        "Actions" => actions: Vec<String>,
+        "Once" => once: bool,
+        "OnExit" => on_exit: bool
+    }
+}
+
+impl Trigger
+{
+    /// Reads a trigger from the fields of its LDtk entity.
+    pub fn from_entity(entity_instance: &EntityInstance, layer_instance: &LayerInstance) -> Trigger
+    {
+        let fields = TriggerFields::from_entity(entity_instance, layer_instance);
+        let (id, visible) = (fields.id, fields.visible);
+        let mut actions = Vec::new();
+        for action in fields.actions
+        {
+            match TriggerAction::parse(&action)
+            {
+                Some(action) => actions.push(action),
+                None => warn!("Trigger {} has an unknown action: {}", id, action)
+            }
+        }
+
+        // Triggers without actions show or hide the text sharing their id.
+        if actions.is_empty()
+        {
+            actions.push(if visible { TriggerAction::ShowText(id) } else { TriggerAction::HideText(id) });
+        }
+
+        Trigger {
+            id,
+            visible,
+            actions,
+            once: fields.once,
+            on_exit: fields.on_exit,
+            ..default()
+        }
+    }
+}
+
+impl LdtkEntity for TriggerBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        _: &AssetServer,
+        _: &mut Assets<TextureAtlas>,
+    ) -> TriggerBundle {
+        let sprite = Sprite {
+            custom_size: Some(Vec2::new(entity_instance.width as f32,entity_instance.height as f32)),
+            color: Color::Rgba { red: 0., green: 0., blue: 0., alpha: 0. },
+            ..Default::default()
+        };
+
+        TriggerBundle {
+            trigger: Trigger::from_entity(entity_instance, layer_instance),
+            sprite: SpriteBundle{sprite,..default()}
+        }
+    }
+}
+
+/// Sent when a player enters a [Trigger], or leaves one set to fire on exit.
+#[derive(Debug, Clone, Copy)]
+pub struct TriggerFired
+{
+    pub id: u32,
+    pub entity: Entity
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, SystemLabel)]
+pub enum TriggerLabel
+{
+    /// [TriggerFired] events for this frame are sent after this.
+    Fire
+}
+
+/// Named story flags set by triggers.
+#[derive(Resource, Default)]
+pub struct GameFlags(pub HashSet<String>);
+
+/// Registers LDtk `Trigger` entities, fires [TriggerFired] and carries out their actions.
+///
+/// Other systems can listen for [TriggerFired] to react to a trigger id on their own.
+pub struct TriggersPlugin;
+
+impl Plugin for TriggersPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.register_ldtk_entity::<TriggerBundle>("Trigger")
+            .add_event::<TriggerFired>()
+            .init_resource::<GameFlags>()
+            .add_system(trigger.run_in_state(GameState::Gameplay).label(TriggerLabel::Fire))
+            .add_system(trigger_actions.run_in_state(GameState::Gameplay).after(TriggerLabel::Fire));
+    }
+}
+
+pub fn trigger(player_q: Query<&Transform, (With<Player>, Without<Dead>)>, mut trigger_q: Query<(&Transform, &mut Trigger, &Sprite, Entity), Without<Player>>, mut fired: EventWriter<TriggerFired>)
+{
+    for (t_transform, mut t_trigger, t_sprite, t_entity) in trigger_q.iter_mut()
+    {
+        let occupied = player_q.iter().any(|p_transform| collide(
+                p_transform.translation,
+               Vec2::new(16.,32.),
+                t_transform.translation,
+              t_sprite.custom_size.unwrap()
+            ).is_some());
+        let crossed = if t_trigger.on_exit
+        {
+            t_trigger.occupied && !occupied
+        }
+        else
+        {
+            !t_trigger.occupied && occupied
+        };
+        t_trigger.occupied = occupied;
+
+        if crossed && !(t_trigger.once && t_trigger.fired)
+        {
+            t_trigger.fired = true;
+            fired.send(TriggerFired { id: t_trigger.id, entity: t_entity });
+        }
+    }
+}
+
+pub fn trigger_actions(
+    mut fired: EventReader<TriggerFired>,
+    trigger_q: Query<(&Trigger, &Transform, Option<&Parent>)>,
+    mut text_q: Query<&mut InGameText>,
+    mut door_q: Query<&mut Door>,
+    mut flags: ResMut<GameFlags>,
+    audio: Option<Res<Audio>>,
+    sounds: Res<AudioAssets>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands)
+{
+    for event in fired.iter()
+    {
+        if let Ok((t_trigger, t_transform, t_parent)) = trigger_q.get(event.entity)
+        {
+            for action in t_trigger.actions.iter()
+            {
+                match action
+                {
+                    TriggerAction::ShowText(text_id) | TriggerAction::HideText(text_id) =>
+                    {
+                        for mut tx_text in text_q.iter_mut()
+                        {
+                            if tx_text.text_id == *text_id
+                            {
+                                tx_text.visible = matches!(action, TriggerAction::ShowText(_));
+                            }
+                        }
+                    },
+                    TriggerAction::OpenDoor(level_id) =>
+                    {
+                        for mut door in door_q.iter_mut()
+                        {
+                            if door.id == *level_id
+                            {
+                                door.open = true;
+                            }
+                        }
+                    },
+                    TriggerAction::SpawnEnemy(offset) =>
+                    {
+                        let mut enemy = EnemyBundle::new(
+                            Enemy { tangible: true, smart: true, ..default() },
+                            MovementOverride::default(),
+                            &asset_server
+                        );
+                        enemy.sprite_bundle.transform.translation = t_transform.translation+(*offset*TILE_SIZE).extend(0.);
+                        // Spawn into the trigger's level so it goes away with it.
+                        match t_parent
+                        {
+                            Some(parent) => { commands.entity(parent.get()).with_children(|level| { level.spawn(enemy); }); },
+                            None => { commands.spawn(enemy); }
+                        }
+                    },
+                    TriggerAction::PlaySound(path) =>
+                    {
+                        if let Some(audio) = &audio
+                        {
+                            let sound = sounds.sounds.get(path).cloned().unwrap_or_else(|| asset_server.load(path.as_str()));
+                            audio.play_with_settings(sound, PlaybackSettings::ONCE.with_volume(settings.volume));
+                        }
+                    },
+                    TriggerAction::SetFlag(flag) =>
+                    {
+                        flags.0.insert(flag.to_owned());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn parses_every_verb()
+    {
+        assert_eq!(TriggerAction::parse("show_text 3"), Some(TriggerAction::ShowText(3)));
+        assert_eq!(TriggerAction::parse("hide_text 3"), Some(TriggerAction::HideText(3)));
+        assert_eq!(TriggerAction::parse("open_door Home"), Some(TriggerAction::OpenDoor("Home".to_string())));
+        assert_eq!(TriggerAction::parse("spawn_enemy -2 1.5"), Some(TriggerAction::SpawnEnemy(Vec2::new(-2., 1.5))));
+        assert_eq!(TriggerAction::parse("play_sound bell.ogg"), Some(TriggerAction::PlaySound("bell.ogg".to_string())));
+        assert_eq!(TriggerAction::parse("set_flag met_guide"), Some(TriggerAction::SetFlag("met_guide".to_string())));
+    }
+
+    #[test]
+    fn enemies_spawn_on_the_trigger_without_an_offset()
+    {
+        assert_eq!(TriggerAction::parse("spawn_enemy"), Some(TriggerAction::SpawnEnemy(Vec2::ZERO)));
+        assert_eq!(TriggerAction::parse("spawn_enemy 2"), Some(TriggerAction::SpawnEnemy(Vec2::new(2., 0.))));
+    }
+
+    #[test]
+    fn ignores_extra_whitespace()
+    {
+        assert_eq!(TriggerAction::parse("  show_text   4 "), Some(TriggerAction::ShowText(4)));
+    }
+
+    #[test]
+    fn rejects_unknown_verbs_and_bad_arguments()
+    {
+        assert_eq!(TriggerAction::parse(""), None);
+        assert_eq!(TriggerAction::parse("dance"), None);
+        assert_eq!(TriggerAction::parse("show_text"), None);
+        assert_eq!(TriggerAction::parse("show_text three"), None);
+        assert_eq!(TriggerAction::parse("hide_text -1"), None);
+        assert_eq!(TriggerAction::parse("spawn_enemy left"), None);
+        assert_eq!(TriggerAction::parse("open_door"), None);
+        assert_eq!(TriggerAction::parse("set_flag"), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! The fire, earth and water powers, granted straight to the player of `Yard`.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::abilities::{Abilities, EarthBlock};
+use pillars_of_nature::collision::CollisionGrid;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::interactables::Enemy;
+use pillars_of_nature::player::Player;
+use pillars_of_nature::projectile::Projectile;
+use pillars_of_nature::TILE_SIZE;
+
+use common::*;
+
+fn grant(app: &mut HeadlessApp, power: impl FnOnce(&mut Abilities))
+{
+    let world = app.world_mut();
+    power(&mut world.query_filtered::<&mut Abilities, With<Player>>().single_mut(world));
+}
+
+fn exists(app: &HeadlessApp, entity: Entity) -> bool
+{
+    app.world().get_entity(entity).is_some()
+}
+
+#[test]
+fn fire_shots_kill_the_enemy_ahead()
+{
+    let mut app = yard();
+    app.step(5);
+    let enemy = spawn_enemy(&mut app, Enemy { tangible: true, ..default() }, YARD_SPAWN-Vec2::new(160., 0.));
+
+    // Nothing comes out without the power.
+    tap(&mut app, KeyCode::F);
+    assert_eq!(count::<With<Projectile>>(&mut app), 0);
+
+    grant(&mut app, |abilities| abilities.fire = true);
+    tap(&mut app, KeyCode::A);
+    tap(&mut app, KeyCode::F);
+    assert_eq!(count::<With<Projectile>>(&mut app), 1);
+    app.step(30);
+    assert!(!exists(&app, enemy), "the shot missed");
+    assert_eq!(count::<With<Projectile>>(&mut app), 0, "the shot went on after the hit");
+}
+
+#[test]
+fn ground_pounds_squash_enemies_next_to_the_landing_spot()
+{
+    let mut app = yard();
+    app.step(5);
+    grant(&mut app, |abilities| abilities.earth = true);
+    let near = spawn_enemy(&mut app, Enemy { tangible: true, ..default() }, YARD_SPAWN+Vec2::new(40., 0.));
+    let far = spawn_enemy(&mut app, Enemy { tangible: true, ..default() }, YARD_SPAWN-Vec2::new(96., 0.));
+    // Anything the enemy carries goes with it.
+    let carried = app.world_mut().spawn(SpatialBundle::default()).id();
+    app.world_mut().entity_mut(near).push_children(&[carried]);
+
+    app.press(KeyCode::Space);
+    app.step(10);
+    app.release(KeyCode::Space);
+    tap(&mut app, KeyCode::S);
+    app.step(20);
+
+    assert_eq!(player(&mut app), YARD_SPAWN);
+    assert!(!exists(&app, near));
+    assert!(!exists(&app, carried));
+    assert!(exists(&app, far));
+}
+
+#[test]
+fn earth_blocks_are_solid_and_only_one_stands_at_a_time()
+{
+    let mut app = yard();
+    app.step(5);
+    grant(&mut app, |abilities| abilities.earth = true);
+    tap(&mut app, KeyCode::Q);
+    let cell = |app: &mut HeadlessApp|
+    {
+        let world = app.world_mut();
+        world.query::<&EarthBlock>().single(world).cell
+    };
+    let first = cell(&mut app);
+    // The cell right of the player's feet, as the player starts out facing right.
+    assert_eq!(first, ((YARD_SPAWN-Vec2::splat(TILE_SIZE/2.))/TILE_SIZE).round().as_ivec2()+IVec2::new(1, 0));
+    assert!(app.world().resource::<CollisionGrid>().is_solid(first.x, first.y));
+
+    // It stops the player like any wall.
+    app.press(KeyCode::D);
+    app.step(20);
+    app.release(KeyCode::D);
+    assert_eq!(player(&mut app), YARD_SPAWN);
+
+    tap(&mut app, KeyCode::A);
+    tap(&mut app, KeyCode::Q);
+    assert_eq!(count::<With<EarthBlock>>(&mut app), 1);
+    let second = cell(&mut app);
+    assert!(second.x < first.x);
+    let grid = app.world().resource::<CollisionGrid>();
+    assert!(!grid.is_solid(first.x, first.y));
+    assert!(grid.is_solid(second.x, second.y));
+}
+
+#[test]
+fn water_slows_the_slide_down_walls()
+{
+    let mut app = yard();
+    app.step(5);
+    // A wall two tiles right of the spawn, from the floor up to the ceiling.
+    let mut grid = app.world_mut().resource_mut::<CollisionGrid>();
+    for y in 4..16
+    {
+        grid.set_solid(27, y, true);
+    }
+
+    let fall_speed = |app: &mut HeadlessApp, water: bool|
+    {
+        grant(app, |abilities| abilities.water = water);
+        teleport(app, Vec2::new(YARD_SPAWN.x, 400.));
+        app.press(KeyCode::D);
+        app.step(25);
+        let before = player(app).y;
+        app.step(1);
+        let speed = before-player(app).y;
+        app.release(KeyCode::D);
+        app.step(120);
+        speed
+    };
+    let dry = fall_speed(&mut app, false);
+    let wet = fall_speed(&mut app, true);
+    assert!(dry > 2., "fell {} px in a tick without water", dry);
+    // The slide speed of 2 px per tick, plus the gravity of the tick that moves the player.
+    assert!(wet <= 3., "slid {} px in a tick", wet);
+}
@@ -0,0 +1,85 @@
+//! Lighting checkpoints and respawning at the lit one of the current level.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::interactables::{Checkpoint, LitCheckpoints, CHECKPOINT_LIT, CHECKPOINT_UNLIT};
+use pillars_of_nature::level::ActiveLevel;
+
+use common::*;
+
+/// `CaveEntrance`, the only level next to `Yard` with a checkpoint.
+const CAVE_ENTRANCE_UID: i32 = 41;
+
+fn checkpoint(app: &mut HeadlessApp) -> (Vec2, bool, Color)
+{
+    let world = app.world_mut();
+    let (c_transform, c_checkpoint, c_sprite) = world.query::<(&Transform, &Checkpoint, &Sprite)>().single(world);
+    (c_transform.translation.truncate(), c_checkpoint.lit, c_sprite.color)
+}
+
+fn fall_to_death(app: &mut HeadlessApp)
+{
+    let bottom = app.world().resource::<ActiveLevel>().bounds.unwrap().min.y;
+    let x = player(app).x;
+    teleport(app, Vec2::new(x, bottom-40.));
+    app.step(60);
+}
+
+/// Walks from `Yard` through its left door into `CaveEntrance`.
+fn enter_cave_entrance(app: &mut HeadlessApp)
+{
+    teleport(app, Vec2::new(64., 200.));
+    walk_into(app, KeyCode::A, CAVE_ENTRANCE_IID);
+}
+
+#[test]
+fn touching_a_checkpoint_lights_it_and_players_respawn_there()
+{
+    let mut app = yard();
+    enter_cave_entrance(&mut app);
+    let (at, lit, color) = checkpoint(&mut app);
+    assert!(!lit);
+    assert_eq!(color, CHECKPOINT_UNLIT);
+    assert!(app.world().resource::<LitCheckpoints>().per_level.is_empty());
+
+    teleport(&mut app, at+Vec2::new(64., 0.));
+    app.press(KeyCode::A);
+    app.step(20);
+    app.release(KeyCode::A);
+    let (_, lit, color) = checkpoint(&mut app);
+    assert!(lit);
+    assert_eq!(color, CHECKPOINT_LIT);
+    let lit_checkpoints = app.world().resource::<LitCheckpoints>();
+    assert_eq!(lit_checkpoints.per_level.get(&CAVE_ENTRANCE_UID), Some(&0));
+    assert_eq!(lit_checkpoints.latest, Some((CAVE_ENTRANCE_UID, 0)));
+
+    teleport(&mut app, at+Vec2::new(160., 0.));
+    fall_to_death(&mut app);
+    assert_eq!(player(&mut app), at);
+}
+
+#[test]
+fn checkpoints_of_other_levels_are_no_respawn_points()
+{
+    let mut app = start(HeadlessApp::new().with_world_streaming());
+    // Levels are laid out like in the LDtk world, so `Yard` is not at the origin.
+    let spawn = player(&mut app);
+
+    // Light the checkpoint of the neighbouring level and walk back into `Yard`, which keeps
+    // that level loaded.
+    let (at, _, _) = checkpoint(&mut app);
+    teleport(&mut app, at);
+    app.step(5);
+    assert_eq!(app.world().resource::<LitCheckpoints>().per_level.get(&CAVE_ENTRANCE_UID), Some(&0));
+    teleport(&mut app, spawn);
+    app.step(30);
+    let yard = app.world().resource::<ActiveLevel>().bounds.unwrap();
+    assert!(yard.contains(spawn));
+    assert!(!yard.contains(at));
+    assert!(checkpoint(&mut app).1, "the neighbouring level was unloaded");
+
+    fall_to_death(&mut app);
+    assert_eq!(player(&mut app), spawn);
+}
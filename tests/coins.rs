@@ -0,0 +1,62 @@
+//! Picking up coins and keeping them picked up across levels.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::interactables::Coin;
+use pillars_of_nature::score::Wallet;
+
+use common::*;
+
+/// Runs left from the spawn until the first coin there, which lies on the floor, is picked up.
+fn pick_up_first_coin(app: &mut HeadlessApp)
+{
+    app.press(KeyCode::A);
+    for _ in 0..150
+    {
+        app.step(1);
+        if app.world().resource::<Wallet>().coins > 0
+        {
+            break;
+        }
+    }
+    app.release(KeyCode::A);
+    app.step(5);
+}
+
+#[test]
+fn coins_are_picked_up_once()
+{
+    let mut app = yard();
+    let before = count::<With<Coin>>(&mut app);
+    assert_eq!(app.world().resource::<Wallet>().coins, 0);
+
+    pick_up_first_coin(&mut app);
+
+    let wallet = app.world().resource::<Wallet>();
+    assert_eq!(wallet.coins, 1);
+    assert_eq!(wallet.collected.values().map(|coins| coins.len()).sum::<usize>(), 1);
+    assert_eq!(count::<With<Coin>>(&mut app), before-1);
+}
+
+#[test]
+fn collected_coins_stay_collected_when_coming_back()
+{
+    let mut app = yard();
+    let before = count::<With<Coin>>(&mut app);
+    pick_up_first_coin(&mut app);
+
+    // Through the door right of the spawn and back, which spawns `Yard` anew.
+    walk_into(&mut app, KeyCode::D, HOME_IID);
+    // The door players come out at stays shut until they step off it.
+    app.press(KeyCode::D);
+    app.step(20);
+    app.release(KeyCode::D);
+    walk_into(&mut app, KeyCode::A, YARD_IID);
+
+    assert_eq!(count::<With<Coin>>(&mut app), before-1);
+    let wallet = app.world().resource::<Wallet>();
+    assert_eq!(wallet.coins, 1);
+    assert_eq!(wallet.collected_in(YARD_IID), 1);
+}
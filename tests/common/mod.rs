@@ -0,0 +1,97 @@
+//! Helpers shared by the integration tests, which play the game headlessly through
+//! [HeadlessApp]. Positions are in pixels in the frame of the world entity, one update is one
+//! physics tick.
+
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::interactables::{Enemy, EnemyBundle};
+use pillars_of_nature::level::ActiveLevel;
+use pillars_of_nature::movement::MovementOverride;
+use pillars_of_nature::physics::Interpolated;
+use pillars_of_nature::player::Player;
+
+/// Where the `PlayerSpawn` of `Yard` puts the player, standing on the floor.
+pub const YARD_SPAWN: Vec2 = Vec2::new(792., 144.);
+pub const YARD_IID: &str = "0cc4ede1-5110-11ed-a4b6-19e14feeb995";
+pub const HOME_IID: &str = "589e56f0-7820-11ed-94b3-0b52e21a75fc";
+pub const CAVE_ENTRANCE_IID: &str = "30e733f0-7820-11ed-94b3-5b2d516a7022";
+
+/// Runs `app` until the level is being played.
+pub fn start(mut app: HeadlessApp) -> HeadlessApp
+{
+    assert!(app.run_until_gameplay(Duration::from_secs(30)), "the game never reached Gameplay");
+    app
+}
+
+/// A game in `Yard`, ready to be played.
+pub fn yard() -> HeadlessApp
+{
+    start(HeadlessApp::new())
+}
+
+pub fn player(app: &mut HeadlessApp) -> Vec2
+{
+    let world = app.world_mut();
+    world.query_filtered::<&Transform, With<Player>>().single(world).translation.truncate()
+}
+
+/// Puts the player at `to` without them travelling there.
+pub fn teleport(app: &mut HeadlessApp, to: Vec2)
+{
+    let world = app.world_mut();
+    let (mut p_transform, mut interpolated) = world.query_filtered::<(&mut Transform, &mut Interpolated), With<Player>>().single_mut(world);
+    p_transform.translation = to.extend(p_transform.translation.z);
+    interpolated.previous = p_transform.translation;
+    interpolated.current = p_transform.translation;
+}
+
+/// Holds `key` until the level with `iid` is being played, then lets go and waits for the
+/// door's fade to end.
+pub fn walk_into(app: &mut HeadlessApp, key: KeyCode, iid: &str)
+{
+    app.press(key);
+    for _ in 0..300
+    {
+        app.step(1);
+        if app.world().resource::<ActiveLevel>().iid.as_deref() == Some(iid)
+        {
+            break;
+        }
+    }
+    app.release(key);
+    app.step(60);
+    assert_eq!(app.world().resource::<ActiveLevel>().iid.as_deref(), Some(iid));
+}
+
+/// Number of entities matching the query filter `F`.
+pub fn count<F: bevy::ecs::query::ReadOnlyWorldQuery>(app: &mut HeadlessApp) -> usize
+{
+    let world = app.world_mut();
+    world.query_filtered::<(), F>().iter(world).count()
+}
+
+/// Taps `key`: held for one tick and let go on the next.
+pub fn tap(app: &mut HeadlessApp, key: KeyCode)
+{
+    app.press(key);
+    app.step(1);
+    app.release(key);
+    app.step(1);
+}
+
+/// Puts `enemy` into the active level at `at`, as a trigger would.
+pub fn spawn_enemy(app: &mut HeadlessApp, enemy: Enemy, at: Vec2) -> Entity
+{
+    let level = app.world().resource::<ActiveLevel>().entity.unwrap();
+    let asset_server = app.world().resource::<AssetServer>().clone();
+    let mut bundle = EnemyBundle::new(enemy, MovementOverride::default(), &asset_server);
+    bundle.sprite_bundle.transform.translation = at.extend(0.);
+    let world = app.world_mut();
+    let e_entity = world.spawn(bundle).id();
+    world.entity_mut(level).push_children(&[e_entity]);
+    e_entity
+}
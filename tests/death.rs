@@ -0,0 +1,46 @@
+//! Dying, the freeze that follows and coming back at the level's `PlayerSpawn`.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::death::{DeathCause, Dead};
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::player::Player;
+
+use common::*;
+
+fn death_cause(app: &mut HeadlessApp) -> Option<DeathCause>
+{
+    let world = app.world_mut();
+    world.query_filtered::<&Dead, With<Player>>().get_single(world).ok().map(|dead| dead.cause)
+}
+
+#[test]
+fn falling_out_of_the_level_kills_and_respawns()
+{
+    let mut app = yard();
+    app.step(5);
+    teleport(&mut app, Vec2::new(YARD_SPAWN.x, -40.));
+    app.step(2);
+    assert_eq!(death_cause(&mut app), Some(DeathCause::Fall));
+
+    app.step(60);
+    assert_eq!(count::<(With<Player>, With<Dead>)>(&mut app), 0, "the player never respawned");
+    assert_eq!(player(&mut app), YARD_SPAWN);
+}
+
+#[test]
+fn dead_players_stay_frozen()
+{
+    let mut app = yard();
+    app.step(5);
+    teleport(&mut app, Vec2::new(YARD_SPAWN.x, -40.));
+    app.step(2);
+    let died_at = player(&mut app);
+
+    app.press(KeyCode::D);
+    app.press(KeyCode::Space);
+    app.step(20);
+    assert!(death_cause(&mut app).is_some());
+    assert_eq!(player(&mut app), died_at);
+}
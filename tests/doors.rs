@@ -0,0 +1,129 @@
+//! Going through doors: where players come out, the fade in between and the cooldown after.
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use pillars_of_nature::GameState;
+use pillars_of_nature::doors::{DoorPhase, DoorTransition};
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::input::PlayerInputs;
+use pillars_of_nature::interactables::{Door, DoorTarget};
+use pillars_of_nature::level::ActiveLevel;
+
+use common::*;
+
+/// The door in `Home` that the `Yard` door to the right of the spawn leads to.
+const HOME_DOOR_X: f32 = 272.;
+/// `CaveFall`, whose door with ID 1 the one on the left of `CaveEntrance` leads to.
+const CAVE_FALL_IID: &str = "29ecd830-7820-11ed-9bd7-a12cd0fa36a8";
+/// Center of that door in `CaveFall`.
+const CAVE_FALL_DOOR_X: f32 = 496.;
+
+fn active_level(app: &HeadlessApp) -> Option<&str>
+{
+    app.world().resource::<ActiveLevel>().iid.as_deref()
+}
+
+#[test]
+fn doors_lead_to_the_linked_door()
+{
+    let mut app = yard();
+    app.step(5);
+    app.press(KeyCode::D);
+    let mut states = Vec::new();
+    for _ in 0..180
+    {
+        app.step(1);
+        let state = app.state().unwrap();
+        if states.last() != Some(&state)
+        {
+            states.push(state);
+        }
+        if active_level(&app) == Some(HOME_IID)
+        {
+            app.release(KeyCode::D);
+        }
+    }
+
+    assert_eq!(states, [GameState::Gameplay, GameState::MapLoad, GameState::Gameplay]);
+    assert_eq!(*app.world().resource::<LevelSelection>(), LevelSelection::Iid(HOME_IID.to_string()));
+    assert_eq!(active_level(&app), Some(HOME_IID));
+    // Let go while input was still locked for the fade, so the player stayed at the door.
+    assert_eq!(player(&mut app).x, HOME_DOOR_X);
+}
+
+#[test]
+fn doors_lead_to_the_door_with_their_target_id()
+{
+    let mut app = yard();
+    teleport(&mut app, Vec2::new(64., 200.));
+    walk_into(&mut app, KeyCode::A, CAVE_ENTRANCE_IID);
+    teleport(&mut app, Vec2::new(64., 112.));
+    walk_into(&mut app, KeyCode::A, CAVE_FALL_IID);
+    assert_eq!(player(&mut app).x, CAVE_FALL_DOOR_X);
+}
+
+#[test]
+fn doors_into_their_own_level_come_out_at_the_spawn()
+{
+    let mut app = yard();
+    let world = app.world_mut();
+    for mut door in world.query::<&mut Door>().iter_mut(world)
+    {
+        door.id = "Yard".to_string();
+        door.target = DoorTarget::PlayerSpawn;
+    }
+    app.step(5);
+    app.press(KeyCode::D);
+    app.step(40);
+    app.release(KeyCode::D);
+    app.step(120);
+
+    // The level is not spawned again, so this must not wait for it in MapLoad.
+    assert_eq!(app.state(), Some(GameState::Gameplay));
+    assert_eq!(player(&mut app), YARD_SPAWN);
+}
+
+#[test]
+fn input_is_locked_while_the_screen_fades()
+{
+    let mut app = yard();
+    app.step(5);
+    app.press(KeyCode::D);
+    for _ in 0..60
+    {
+        app.step(1);
+        if app.world().resource::<DoorTransition>().phase == DoorPhase::FadeOut
+        {
+            break;
+        }
+    }
+    assert_eq!(app.world().resource::<DoorTransition>().phase, DoorPhase::FadeOut);
+    assert!(app.world().resource::<PlayerInputs>().locked);
+    // The speed from before the lock can still carry the player a tick or two.
+    app.step(2);
+
+    // Still holding D, but the player stays in front of the door.
+    let entered = player(&mut app);
+    app.step(10);
+    assert_eq!(player(&mut app), entered);
+    app.release(KeyCode::D);
+}
+
+#[test]
+fn players_do_not_bounce_back_through_the_door_they_came_out_of()
+{
+    let mut app = yard();
+    walk_into(&mut app, KeyCode::D, HOME_IID);
+    // Standing on the door long after the cooldown is over.
+    app.step(120);
+    assert_eq!(active_level(&app), Some(HOME_IID));
+    assert!(!app.world().resource::<DoorTransition>().active());
+
+    // Once off it, the door takes them back.
+    app.press(KeyCode::D);
+    app.step(20);
+    app.release(KeyCode::D);
+    walk_into(&mut app, KeyCode::A, YARD_IID);
+}
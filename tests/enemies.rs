@@ -0,0 +1,92 @@
+//! Enemy patrols and chases, on the floor of `Yard`.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::collision::CollisionGrid;
+use pillars_of_nature::enemy::{Patrol, SIGHT_RADIUS};
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::interactables::Enemy;
+
+use common::*;
+
+/// Far enough left of the spawn that enemies here don't see the player.
+const FLOOR: Vec2 = Vec2::new(400., 144.);
+
+fn enemy_x(app: &HeadlessApp, e_entity: Entity) -> f32
+{
+    app.world().get::<Transform>(e_entity).unwrap().translation.x
+}
+
+/// Leftmost and rightmost x the enemy reaches over `ticks` updates.
+fn range(app: &mut HeadlessApp, e_entity: Entity, ticks: usize) -> (f32, f32)
+{
+    (0..ticks).fold((f32::MAX, f32::MIN), |(min, max), _|
+    {
+        app.step(1);
+        let x = enemy_x(app, e_entity);
+        (min.min(x), max.max(x))
+    })
+}
+
+#[test]
+fn enemies_patrol_over_their_move_distance()
+{
+    let mut app = yard();
+    app.step(5);
+    const { assert!(YARD_SPAWN.x-FLOOR.x > SIGHT_RADIUS) };
+    let e_entity = spawn_enemy(&mut app, Enemy { move_distance: Vec2::new(3., 0.), move_speed: Vec2::new(4., 0.), tangible: true, smart: true }, FLOOR);
+    app.step(1);
+    assert_eq!(app.world().get::<Patrol>(e_entity).unwrap().origin.truncate(), FLOOR);
+
+    // Three tiles at four tiles a second, there and back twice.
+    let (min, max) = range(&mut app, e_entity, 180);
+    assert!((min-FLOOR.x).abs() <= 4., "went left to {}", min);
+    assert!((max-(FLOOR.x+96.)).abs() <= 4., "went right to {}", max);
+}
+
+#[test]
+fn smart_enemies_turn_at_walls()
+{
+    let mut app = yard();
+    app.step(5);
+    // A wall two tiles right of where the enemy starts, facing the steps at its back.
+    let mut grid = app.world_mut().resource_mut::<CollisionGrid>();
+    for y in 4..7
+    {
+        grid.set_solid(15, y, true);
+    }
+    let e_entity = spawn_enemy(&mut app, Enemy { move_speed: Vec2::new(4., 0.), tangible: true, smart: true, ..default() }, FLOOR);
+
+    let mut xs = vec![enemy_x(&app, e_entity)];
+    for _ in 0..120
+    {
+        app.step(1);
+        xs.push(enemy_x(&app, e_entity));
+    }
+    let steps: Vec<f32> = xs.windows(2).map(|pair| pair[1]-pair[0]).filter(|step| *step != 0.).collect();
+    let turns = steps.windows(2).filter(|pair| pair[0].signum() != pair[1].signum()).count();
+    assert!(turns >= 3, "turned {} times", turns);
+    let (min, max) = xs.iter().fold((f32::MAX, f32::MIN), |(min, max), x| (min.min(*x), max.max(*x)));
+    // Its right edge stays left of the wall, its left edge right of the steps. Cell x spans
+    // from x*32 to x*32+32 in the frame of entities.
+    assert!(max+16. <= 15.*32., "walked into the wall at {}", max);
+    assert!(min-16. >= 12.*32., "walked into the steps at {}", min);
+}
+
+#[test]
+fn smart_enemies_chase_players_they_see()
+{
+    let mut app = yard();
+    app.step(5);
+    let near = YARD_SPAWN-Vec2::new(SIGHT_RADIUS-32., 0.);
+    let smart = spawn_enemy(&mut app, Enemy { move_distance: Vec2::new(-2., 0.), move_speed: Vec2::new(2., 0.), tangible: false, smart: true }, near);
+    let dumb = spawn_enemy(&mut app, Enemy { move_distance: Vec2::new(-2., 0.), move_speed: Vec2::new(2., 0.), tangible: false, smart: false }, near);
+    app.step(60);
+
+    // The patrol leads away from the player, so only a chase gets closer.
+    assert!(enemy_x(&app, smart) > near.x+32., "the smart enemy stayed at {}", enemy_x(&app, smart));
+    assert!(app.world().get::<Patrol>(smart).unwrap().chasing);
+    assert!(enemy_x(&app, dumb) < near.x);
+    assert!(!app.world().get::<Patrol>(dumb).unwrap().chasing);
+}
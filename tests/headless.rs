@@ -0,0 +1,31 @@
+//! The headless harness itself: the game gets to a playable level without a window.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::GameState;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::level::ActiveLevel;
+use pillars_of_nature::player::Player;
+
+use common::*;
+
+#[test]
+fn reaches_gameplay_at_the_player_spawn()
+{
+    let mut app = yard();
+    assert_eq!(app.state(), Some(GameState::Gameplay));
+    assert_eq!(count::<With<Player>>(&mut app), 1);
+    assert_eq!(app.world().resource::<ActiveLevel>().bounds, Some(Rect::new(0., 0., 1024., 608.)));
+
+    // Nothing moves a player left alone on the floor.
+    app.step(30);
+    assert_eq!(player(&mut app), YARD_SPAWN);
+}
+
+#[test]
+fn starts_in_the_level_it_is_given()
+{
+    let app = start(HeadlessApp::new().with_level("Home"));
+    assert_eq!(app.world().resource::<ActiveLevel>().iid.as_deref(), Some(HOME_IID));
+}
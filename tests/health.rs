@@ -0,0 +1,117 @@
+//! Hearts, the invulnerability after a hit and how spikes hurt.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::death::{DeathCause, Dead};
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::health::{Health, Invulnerable, LevelHazards, INVULNERABLE_TIME, PLAYER_MAX_HEALTH};
+use pillars_of_nature::interactables::{Direction, Enemy, Killer};
+use pillars_of_nature::level::ActiveLevel;
+use pillars_of_nature::player::Player;
+
+use common::*;
+
+fn health(app: &mut HeadlessApp) -> u32
+{
+    let world = app.world_mut();
+    world.query_filtered::<&Health, With<Player>>().single(world).current
+}
+
+fn invulnerable(app: &mut HeadlessApp) -> bool
+{
+    count::<(With<Player>, With<Invulnerable>)>(app) == 1
+}
+
+fn death_cause(app: &mut HeadlessApp) -> Option<DeathCause>
+{
+    let world = app.world_mut();
+    world.query_filtered::<&Dead, With<Player>>().get_single(world).ok().map(|dead| dead.cause)
+}
+
+/// Upward spikes under the player standing at the spawn.
+fn place_spikes(app: &mut HeadlessApp) -> Entity
+{
+    let level = app.world().resource::<ActiveLevel>().entity.unwrap();
+    let world = app.world_mut();
+    let spikes = world.spawn((
+        Killer(Direction::North),
+        Transform::from_translation(Vec3::new(YARD_SPAWN.x-16., YARD_SPAWN.y-22., 0.))
+    )).id();
+    world.entity_mut(level).push_children(&[spikes]);
+    spikes
+}
+
+#[test]
+fn enemies_cost_a_heart_then_leave_the_player_alone_for_a_while()
+{
+    let mut app = yard();
+    app.step(5);
+    assert_eq!(health(&mut app), PLAYER_MAX_HEALTH);
+    let enemy_at = YARD_SPAWN-Vec2::new(20., 0.);
+    spawn_enemy(&mut app, Enemy { tangible: true, ..default() }, enemy_at);
+    app.step(2);
+    assert_eq!(health(&mut app), PLAYER_MAX_HEALTH-1);
+    assert!(invulnerable(&mut app));
+
+    // Thrown back, away from the enemy.
+    app.step(10);
+    assert!(player(&mut app).x > YARD_SPAWN.x);
+
+    // Touching it again while blinking does nothing.
+    teleport(&mut app, enemy_at+Vec2::new(20., 0.));
+    app.step(10);
+    assert_eq!(health(&mut app), PLAYER_MAX_HEALTH-1);
+
+    app.step((INVULNERABLE_TIME*60.) as usize);
+    assert!(!invulnerable(&mut app));
+    teleport(&mut app, enemy_at+Vec2::new(20., 0.));
+    app.step(2);
+    assert_eq!(health(&mut app), PLAYER_MAX_HEALTH-2);
+}
+
+#[test]
+fn players_die_with_their_last_heart_and_come_back_healed()
+{
+    // A hit goes from the spikes through `Damage` and `PlayerDied`, which takes a few ticks.
+    let mut app = yard();
+    app.step(5);
+    let spikes = place_spikes(&mut app);
+    for lost in 1..PLAYER_MAX_HEALTH
+    {
+        teleport(&mut app, YARD_SPAWN);
+        app.step(5);
+        assert_eq!(health(&mut app), PLAYER_MAX_HEALTH-lost);
+        assert_eq!(death_cause(&mut app), None);
+        app.step((INVULNERABLE_TIME*60.) as usize+1);
+    }
+    teleport(&mut app, YARD_SPAWN);
+    app.step(5);
+    assert_eq!(death_cause(&mut app), Some(DeathCause::Spikes));
+
+    // The respawn would put the player right back on them.
+    app.world_mut().entity_mut(spikes).despawn_recursive();
+    app.step(60);
+    assert_eq!(death_cause(&mut app), None);
+    assert_eq!(health(&mut app), PLAYER_MAX_HEALTH);
+}
+
+#[test]
+fn one_hit_spikes_kill_outright()
+{
+    let mut app = yard();
+    app.step(5);
+    assert!(!app.world().resource::<LevelHazards>().one_hit_spikes);
+    app.world_mut().resource_mut::<LevelHazards>().one_hit_spikes = true;
+    place_spikes(&mut app);
+    app.step(5);
+    assert_eq!(death_cause(&mut app), Some(DeathCause::Spikes));
+}
+
+#[test]
+fn levels_choose_whether_spikes_kill_outright()
+{
+    let mut app = start(HeadlessApp::new().with_level("Challenge"));
+    assert!(app.world().resource::<LevelHazards>().one_hit_spikes);
+    assert!(count::<With<Killer>>(&mut app) > 0);
+}
@@ -0,0 +1,80 @@
+//! The title menu and the pause overlay, driven through the keyboard.
+
+mod common;
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use pillars_of_nature::GameState;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::menu::{ActiveMenu, MenuScreen};
+use pillars_of_nature::player::Player;
+
+use common::*;
+
+fn screen(app: &HeadlessApp) -> MenuScreen
+{
+    app.world().resource::<ActiveMenu>().screen
+}
+
+#[test]
+fn escape_pauses_play_until_pressed_again()
+{
+    let mut app = yard();
+    app.step(5);
+    app.press(KeyCode::A);
+    app.step(5);
+
+    tap(&mut app, KeyCode::Escape);
+    assert_eq!(app.state(), Some(GameState::Pause));
+    assert_eq!(screen(&app), MenuScreen::Pause);
+    // Still holding A, but nothing that runs in Gameplay moves the player.
+    let paused = player(&mut app);
+    app.step(30);
+    assert_eq!(player(&mut app), paused);
+
+    tap(&mut app, KeyCode::Escape);
+    assert_eq!(app.state(), Some(GameState::Gameplay));
+    app.step(5);
+    assert!(player(&mut app).x < paused.x, "the player did not run on after the pause");
+}
+
+#[test]
+fn resume_and_settings_in_the_pause_menu()
+{
+    let mut app = yard();
+    app.step(5);
+    tap(&mut app, KeyCode::Escape);
+
+    // Settings is the second item, and going back from it returns to the pause menu.
+    tap(&mut app, KeyCode::Down);
+    tap(&mut app, KeyCode::Return);
+    assert_eq!(screen(&app), MenuScreen::Settings);
+    tap(&mut app, KeyCode::Escape);
+    assert_eq!(screen(&app), MenuScreen::Pause);
+    assert_eq!(app.state(), Some(GameState::Pause));
+
+    tap(&mut app, KeyCode::Return);
+    assert_eq!(app.state(), Some(GameState::Gameplay));
+}
+
+#[test]
+fn new_game_from_the_title_menu_starts_play()
+{
+    let mut app = yard();
+    app.world_mut().insert_resource(NextState(GameState::Menu));
+    app.step(2);
+    assert_eq!(app.state(), Some(GameState::Menu));
+    assert_eq!(screen(&app), MenuScreen::Title);
+
+    // Escape does nothing on the title screen.
+    tap(&mut app, KeyCode::Escape);
+    assert_eq!(app.state(), Some(GameState::Menu));
+
+    tap(&mut app, KeyCode::Return);
+    // New Game goes through Setup, which moves straight on to loading the level.
+    assert!(matches!(app.state(), Some(GameState::Setup | GameState::MapLoad)), "the game is in {:?}", app.state());
+    assert!(app.run_until_gameplay(Duration::from_secs(30)), "the game never reached Gameplay");
+    assert_eq!(count::<With<Player>>(&mut app), 1);
+}
@@ -0,0 +1,109 @@
+//! Running, falling and jumping on the fixed physics timestep, tuned by the [MovementConfig].
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::movement::{MovementConfig, MovementOverride};
+use pillars_of_nature::player::Player;
+
+use common::*;
+
+#[test]
+fn runs_four_pixels_per_tick()
+{
+    let mut app = yard();
+    app.step(5);
+    app.press(KeyCode::A);
+    app.step(2);
+    for _ in 0..20
+    {
+        let before = player(&mut app);
+        app.step(1);
+        assert_eq!(player(&mut app)-before, Vec2::new(-4., 0.));
+    }
+    app.release(KeyCode::A);
+    app.step(2);
+    let stopped = player(&mut app);
+    app.step(5);
+    assert_eq!(player(&mut app), stopped);
+}
+
+#[test]
+fn falls_no_faster_than_terminal_velocity()
+{
+    let mut app = yard();
+    app.step(5);
+    teleport(&mut app, Vec2::new(YARD_SPAWN.x, 560.));
+    let mut drops = Vec::new();
+    while player(&mut app).y > YARD_SPAWN.y
+    {
+        let before = player(&mut app).y;
+        app.step(1);
+        drops.push(before-player(&mut app).y);
+        assert!(drops.len() < 120, "the player never landed");
+    }
+    // 720 px/s at 60 ticks a second, reached after 12 ticks of 3600 px/s² of gravity.
+    assert!(drops.iter().all(|drop| *drop <= 12.), "fell {:?}", drops);
+    assert!(drops.iter().filter(|drop| **drop == 12.).count() > 5, "fell {:?}", drops);
+    app.step(5);
+    assert_eq!(player(&mut app), YARD_SPAWN);
+}
+
+/// Highest the player gets over `ticks` updates.
+fn apex(app: &mut HeadlessApp, ticks: usize) -> f32
+{
+    (0..ticks).map(|_| { app.step(1); player(app).y }).fold(f32::MIN, f32::max)
+}
+
+fn run_distance(app: &mut HeadlessApp) -> f32
+{
+    app.press(KeyCode::A);
+    app.step(2);
+    let before = player(app).x;
+    app.step(1);
+    let distance = before-player(app).x;
+    app.release(KeyCode::A);
+    app.step(2);
+    distance
+}
+
+#[test]
+fn jumps_and_cuts_the_jump_short_when_released()
+{
+    let mut app = yard();
+    app.step(5);
+    app.press(KeyCode::Space);
+    let full = apex(&mut app, 40)-YARD_SPAWN.y;
+    app.release(KeyCode::Space);
+    app.step(30);
+    assert_eq!(player(&mut app), YARD_SPAWN, "the player should land where they jumped");
+
+    app.press(KeyCode::Space);
+    app.step(4);
+    app.release(KeyCode::Space);
+    let cut = apex(&mut app, 40)-YARD_SPAWN.y;
+    app.step(30);
+    assert_eq!(player(&mut app), YARD_SPAWN);
+
+    // 840 px/s against 3600 px/s² of gravity tops out at 98 pixels.
+    assert!((80. ..=100.).contains(&full), "a full jump reached {}", full);
+    assert!(cut > 0. && cut < full*0.75, "a cut jump reached {} against {}", cut, full);
+}
+
+#[test]
+fn follows_the_movement_config_and_overrides()
+{
+    let mut app = yard();
+    app.step(5);
+    // Read from the asset file, which rounds the built-in coyote time.
+    assert_eq!(app.world().resource::<MovementConfig>().coyote_time, 0.0833);
+
+    app.world_mut().resource_mut::<MovementConfig>().run_speed = 120.;
+    assert_eq!(run_distance(&mut app), 2.);
+
+    let world = app.world_mut();
+    let p_entity = world.query_filtered::<Entity, With<Player>>().single(world);
+    world.entity_mut(p_entity).insert(MovementOverride { run_speed: Some(360.), ..default() });
+    assert_eq!(run_distance(&mut app), 6.);
+}
@@ -0,0 +1,114 @@
+//! Projectiles on the physics timestep, and the cooldown of the [Shooter]s firing them.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::abilities::Abilities;
+use pillars_of_nature::collision::CollisionGrid;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::interactables::Enemy;
+use pillars_of_nature::physics::Interpolated;
+use pillars_of_nature::player::Player;
+use pillars_of_nature::projectile::{Projectile, Shooter};
+
+use common::*;
+
+/// Open air above the spawn, clear of the walls of `Yard`.
+const SKY: Vec2 = Vec2::new(640., 400.);
+
+fn shoot(app: &mut HeadlessApp, at: Vec2, vel: Vec2, lifetime: f32, gravity: f32, owner: Option<Entity>) -> Entity
+{
+    let translation = at.extend(0.);
+    app.world_mut().spawn((
+        Projectile {
+            vel,
+            lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+            damage: 1,
+            gravity,
+            collides_with_solids: true,
+            owner
+        },
+        SpriteBundle {
+            sprite: Sprite { custom_size: Some(Vec2::splat(8.)), ..default() },
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        Interpolated { previous: translation, current: translation }
+    )).id()
+}
+
+fn position(app: &HeadlessApp, entity: Entity) -> Option<Vec2>
+{
+    app.world().get::<Transform>(entity).map(|transform| transform.translation.truncate())
+}
+
+#[test]
+fn shooters_wait_for_their_cooldown()
+{
+    let mut app = yard();
+    app.step(5);
+    let world = app.world_mut();
+    world.query_filtered::<&mut Abilities, With<Player>>().single_mut(world).fire = true;
+    let cooldown = world.query_filtered::<&Shooter, With<Player>>().single(world).cooldown.duration().as_secs_f32();
+
+    tap(&mut app, KeyCode::F);
+    tap(&mut app, KeyCode::F);
+    assert_eq!(count::<With<Projectile>>(&mut app), 1);
+
+    app.step((cooldown*60.) as usize);
+    tap(&mut app, KeyCode::F);
+    assert_eq!(count::<With<Projectile>>(&mut app), 2);
+}
+
+#[test]
+fn projectiles_fly_until_they_burn_out()
+{
+    let mut app = yard();
+    app.step(5);
+    let straight = shoot(&mut app, SKY, Vec2::new(120., 0.), 0.5, 0., None);
+    let falling = shoot(&mut app, SKY, Vec2::new(-120., 0.), 0.5, 600., None);
+
+    app.step(10);
+    assert_eq!(position(&app, straight), Some(SKY+Vec2::new(20., 0.)));
+    let arc = position(&app, falling).unwrap();
+    assert_eq!(arc.x, SKY.x-20.);
+    assert!(arc.y < SKY.y);
+
+    app.step(19);
+    assert!(position(&app, straight).is_some());
+    app.step(1);
+    assert!(position(&app, straight).is_none(), "lived past its lifetime");
+    assert!(position(&app, falling).is_none());
+}
+
+#[test]
+fn walls_stop_projectiles()
+{
+    let mut app = yard();
+    app.step(5);
+    let mut grid = app.world_mut().resource_mut::<CollisionGrid>();
+    for y in 10..15
+    {
+        grid.set_solid(22, y, true);
+    }
+    let shot = shoot(&mut app, SKY, Vec2::new(480., 0.), 1., 0., None);
+    app.step(10);
+    assert!(position(&app, shot).is_none(), "flew through the wall");
+}
+
+#[test]
+fn projectiles_hurt_enemies_but_never_their_owner()
+{
+    let mut app = yard();
+    app.step(5);
+    // On the floor left of the spawn.
+    let floor = Vec2::new(560., YARD_SPAWN.y);
+    let owner = spawn_enemy(&mut app, Enemy { tangible: true, ..default() }, floor);
+    let target = spawn_enemy(&mut app, Enemy { tangible: true, ..default() }, floor+Vec2::new(96., 0.));
+    app.step(1);
+    let shot = shoot(&mut app, floor, Vec2::new(480., 0.), 1., 0., Some(owner));
+    app.step(20);
+    assert!(position(&app, shot).is_none());
+    assert!(position(&app, owner).is_some());
+    assert!(position(&app, target).is_none());
+}
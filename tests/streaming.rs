@@ -0,0 +1,80 @@
+//! Walking between levels laid out like in the LDtk world, with [WorldStreaming] on.
+
+mod common;
+
+use bevy::prelude::*;
+use pillars_of_nature::camera::CameraFollow;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::interactables::Door;
+use pillars_of_nature::level::{ActiveLevel, LevelBounds};
+use pillars_of_nature::streaming::WorldStreaming;
+
+use common::*;
+
+const CHALLENGE_IID: &str = "26829930-7820-11ed-9bd7-4369c74f961e";
+const CAVE_FALL_IID: &str = "29ecd830-7820-11ed-9bd7-a12cd0fa36a8";
+
+/// Iids of the levels that are loaded.
+fn loaded_levels(app: &mut HeadlessApp) -> Vec<String>
+{
+    let world = app.world_mut();
+    let mut iids: Vec<String> = world.query::<&LevelBounds>().iter(world).map(|l_bounds| l_bounds.iid.to_owned()).collect();
+    iids.sort();
+    iids
+}
+
+fn active_bounds(app: &HeadlessApp) -> Rect
+{
+    app.world().resource::<ActiveLevel>().bounds.unwrap()
+}
+
+/// Center of the camera's view, which has to stay inside the active level.
+fn camera(app: &mut HeadlessApp) -> Vec2
+{
+    let world = app.world_mut();
+    world.query::<&CameraFollow>().single(world).position
+}
+
+fn sorted(iids: &[&str]) -> Vec<String>
+{
+    let mut iids: Vec<String> = iids.iter().map(|iid| iid.to_string()).collect();
+    iids.sort();
+    iids
+}
+
+#[test]
+fn streaming_is_off_unless_asked_for()
+{
+    let mut app = yard();
+    assert!(!app.world().resource::<WorldStreaming>().0);
+    assert_eq!(loaded_levels(&mut app), [YARD_IID]);
+}
+
+#[test]
+fn walking_off_the_edge_moves_into_the_next_level()
+{
+    let mut app = start(HeadlessApp::new().with_world_streaming());
+    // `Yard` and all of its neighbours are in, with `Yard` in the LDtk world's frame.
+    assert_eq!(loaded_levels(&mut app), sorted(&[YARD_IID, HOME_IID, CAVE_ENTRANCE_IID, CHALLENGE_IID]));
+    let yard = active_bounds(&app);
+    assert_eq!(yard, Rect::new(0., 1728., 1024., 2336.));
+    assert!(yard.contains(camera(&mut app)));
+
+    // Walk out on the left, past the door, which is kept shut so it does not take the player.
+    let world = app.world_mut();
+    for mut door in world.query::<&mut Door>().iter_mut(world)
+    {
+        door.open = false;
+    }
+    teleport(&mut app, Vec2::new(64., 1928.));
+    app.step(10);
+    walk_into(&mut app, KeyCode::A, CAVE_ENTRANCE_IID);
+    assert!(player(&mut app).x < 0.);
+
+    // `Home` and `Challenge` are no longer next to the active level, `CaveFall` now is.
+    assert_eq!(loaded_levels(&mut app), sorted(&[YARD_IID, CAVE_ENTRANCE_IID, CAVE_FALL_IID]));
+    let cave_entrance = active_bounds(&app);
+    assert_eq!(cave_entrance, Rect::new(-512., 1728., 0., 2240.));
+    let view = camera(&mut app);
+    assert!(cave_entrance.contains(view) && !yard.contains(view), "the camera stayed at {}", view);
+}
@@ -0,0 +1,139 @@
+//! Triggers placed in `Yard`, fired by walking the player through them.
+
+mod common;
+
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::interactables::Enemy;
+use pillars_of_nature::level::ActiveLevel;
+use pillars_of_nature::triggers::{GameFlags, Trigger, TriggerAction, TriggerBundle, TriggerFired};
+
+use common::*;
+
+/// Left of the spawn, on the player's way when they run left.
+const TRIGGER_AT: Vec2 = Vec2::new(696., 160.);
+
+/// Puts a trigger into the active level and returns it.
+fn place_trigger(app: &mut HeadlessApp, trigger: Trigger) -> Entity
+{
+    let level = app.world().resource::<ActiveLevel>().entity.unwrap();
+    let world = app.world_mut();
+    let t_entity = world.spawn(TriggerBundle {
+        trigger,
+        sprite: SpriteBundle {
+            sprite: Sprite { custom_size: Some(Vec2::new(32., 64.)), ..default() },
+            transform: Transform::from_translation(TRIGGER_AT.extend(0.)),
+            ..default()
+        }
+    }).id();
+    world.entity_mut(level).push_children(&[t_entity]);
+    t_entity
+}
+
+/// Counts the [TriggerFired] events of trigger `id` while stepping.
+struct Fired
+{
+    reader: ManualEventReader<TriggerFired>,
+    id: u32,
+    count: usize
+}
+
+impl Fired
+{
+    fn new(app: &HeadlessApp, id: u32) -> Fired
+    {
+        Fired { reader: app.world().resource::<Events<TriggerFired>>().get_reader(), id, count: 0 }
+    }
+
+    fn step(&mut self, app: &mut HeadlessApp, ticks: usize)
+    {
+        for _ in 0..ticks
+        {
+            app.step(1);
+            let events = app.world().resource::<Events<TriggerFired>>();
+            self.count += self.reader.iter(events).filter(|event| event.id == self.id).count();
+        }
+    }
+}
+
+/// Runs left from the spawn through the trigger and back out of it.
+fn walk_through(app: &mut HeadlessApp, fired: &mut Fired)
+{
+    teleport(app, YARD_SPAWN);
+    fired.step(app, 2);
+    app.press(KeyCode::A);
+    fired.step(app, 50);
+    app.release(KeyCode::A);
+    fired.step(app, 2);
+}
+
+#[test]
+fn triggers_fire_each_time_a_player_enters()
+{
+    let mut app = yard();
+    place_trigger(&mut app, Trigger { id: 7, actions: vec![TriggerAction::SetFlag("visited".to_string())], ..default() });
+    let mut fired = Fired::new(&app, 7);
+    fired.step(&mut app, 5);
+    assert_eq!(fired.count, 0);
+    assert!(!app.world().resource::<GameFlags>().0.contains("visited"));
+
+    walk_through(&mut app, &mut fired);
+    assert_eq!(fired.count, 1);
+    assert!(app.world().resource::<GameFlags>().0.contains("visited"));
+
+    walk_through(&mut app, &mut fired);
+    assert_eq!(fired.count, 2);
+}
+
+#[test]
+fn once_triggers_fire_only_the_first_time()
+{
+    let mut app = yard();
+    place_trigger(&mut app, Trigger { id: 7, once: true, actions: vec![TriggerAction::SetFlag("visited".to_string())], ..default() });
+    let mut fired = Fired::new(&app, 7);
+    walk_through(&mut app, &mut fired);
+    walk_through(&mut app, &mut fired);
+    assert_eq!(fired.count, 1);
+}
+
+#[test]
+fn exit_triggers_fire_when_the_player_leaves()
+{
+    let mut app = yard();
+    place_trigger(&mut app, Trigger { id: 7, on_exit: true, actions: vec![TriggerAction::SetFlag("left".to_string())], ..default() });
+    let mut fired = Fired::new(&app, 7);
+    teleport(&mut app, TRIGGER_AT-Vec2::new(0., 16.));
+    fired.step(&mut app, 10);
+    assert_eq!(fired.count, 0, "fired while the player was still inside");
+
+    app.press(KeyCode::A);
+    fired.step(&mut app, 20);
+    app.release(KeyCode::A);
+    assert_eq!(fired.count, 1);
+    assert!(app.world().resource::<GameFlags>().0.contains("left"));
+}
+
+#[test]
+fn enemies_spawn_into_the_level_of_the_trigger()
+{
+    let mut app = yard();
+    let t_entity = place_trigger(&mut app, Trigger { id: 7, actions: vec![TriggerAction::SpawnEnemy(Vec2::new(0., 4.))], ..default() });
+    let enemies = count::<With<Enemy>>(&mut app);
+    let mut fired = Fired::new(&app, 7);
+    app.press(KeyCode::A);
+    while fired.count == 0
+    {
+        fired.step(&mut app, 1);
+    }
+    app.release(KeyCode::A);
+    app.step(1);
+
+    assert_eq!(count::<With<Enemy>>(&mut app), enemies+1);
+    // Four tiles above the trigger, give or take the tick it has moved since.
+    let level = app.world().get::<Parent>(t_entity).unwrap().get();
+    let world = app.world_mut();
+    let spawned = world.query_filtered::<(&Parent, &Transform), With<Enemy>>().iter(world)
+        .any(|(parent, e_transform)| parent.get() == level && e_transform.translation.truncate().distance(TRIGGER_AT+Vec2::new(0., 128.)) < 8.);
+    assert!(spawned);
+}
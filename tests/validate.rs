@@ -0,0 +1,94 @@
+//! `validate_project` on small projects in `tests/fixtures`, each breaking one rule, and the
+//! `pillars-validate` binary that runs it.
+
+use std::fs;
+use std::process::Command;
+
+use bevy_ecs_ldtk::app::LdtkEntityMap;
+use bevy_ecs_ldtk::ldtk::LdtkJson;
+use pillars_of_nature::headless::HeadlessApp;
+use pillars_of_nature::validate::{validate_project, Problem};
+
+fn fixture(name: &str) -> String
+{
+    format!("{}/tests/fixtures/{}.ldtk", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn problems(name: &str) -> Vec<Problem>
+{
+    let project: LdtkJson = serde_json::from_str(&fs::read_to_string(fixture(name)).unwrap()).unwrap();
+    let app = HeadlessApp::new();
+    validate_project(&project, app.world().non_send_resource::<LdtkEntityMap>())
+}
+
+/// Messages of the problems, each with its level.
+fn messages(name: &str) -> Vec<(String, String)>
+{
+    problems(name).into_iter().map(|problem| (problem.level, problem.message)).collect()
+}
+
+fn one(level: &str, message: &str) -> Vec<(String, String)>
+{
+    vec![(level.to_string(), message.to_string())]
+}
+
+#[test]
+fn sound_projects_have_no_problems()
+{
+    assert_eq!(problems("valid"), []);
+}
+
+#[test]
+fn doors_to_missing_levels()
+{
+    assert_eq!(messages("missing_level"), one("First", "LvID names a missing level: Nowhere"));
+    assert!(problems("missing_level")[0].entity.as_ref().is_some_and(|entity| entity.starts_with("Door at (224, 192)")));
+}
+
+#[test]
+fn hidden_texts_no_trigger_shows()
+{
+    assert_eq!(messages("orphan_text"), one("First", "hidden, and no trigger in the level shows TextID 1"));
+}
+
+#[test]
+fn levels_without_exactly_one_player_spawn()
+{
+    assert_eq!(messages("no_spawn"), one("First", "has 0 PlayerSpawn entities instead of one"));
+    assert_eq!(messages("two_spawns"), one("First", "has 2 PlayerSpawn entities instead of one"));
+}
+
+#[test]
+fn entities_the_game_never_spawns()
+{
+    assert_eq!(messages("unregistered"), one("First", "Dragon is not registered, so it never spawns"));
+}
+
+fn validate(args: &[&str]) -> i32
+{
+    Command::new(env!("CARGO_BIN_EXE_pillars-validate"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn the_binary_fails_on_problems()
+{
+    assert_eq!(validate(&[&fixture("valid")]), 0);
+    for name in ["missing_level", "orphan_text", "no_spawn", "two_spawns", "unregistered"]
+    {
+        assert_eq!(validate(&[&fixture(name)]), 1, "{} passed", name);
+    }
+    assert_eq!(validate(&[&fixture("missing")]), 2);
+}
+
+#[test]
+fn the_game_project_has_only_known_problems()
+{
+    assert_eq!(validate(&[]), 0);
+}